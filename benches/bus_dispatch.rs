@@ -0,0 +1,86 @@
+/*
+    Compares a CPU that owns its bus, where memory accesses are statically dispatched and inlined,
+    against one going through a shared `Rc<RefCell<dyn Bus>>` like the CPU used to.
+    Run with `cargo bench`.
+*/
+use std::{
+    cell::RefCell,
+    hint::black_box,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use gb_core::{Bus, CPU, Interrupt};
+
+const INSTRUCTIONS: u32 = 20_000_000;
+
+// Flat 64 KiB of RAM, so the bench only measures the CPU and the cost of reaching the bus
+struct FlatBus {
+    memory: Vec<u8>,
+}
+
+impl FlatBus {
+    fn new() -> Self {
+        let mut memory = vec![0; 0x10000];
+
+        // A loop that hammers memory: increments the byte at $C000 forever
+        memory[0x0100..0x0108].copy_from_slice(&[
+            0x21, 0x00, 0xC0, // ld hl, $C000
+            0x2A, // ld a, [hl+]
+            0x3C, // inc a
+            0x32, // ld [hl-], a
+            0x18, 0xFB, // jr $0103
+        ]);
+
+        Self { memory }
+    }
+}
+
+impl Bus for FlatBus {
+    fn read(&self, addr: u16) -> u8 {
+        self.memory[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        self.memory[addr as usize] = data;
+    }
+
+    fn request_interrupt(&mut self, _interrupt: Interrupt) {}
+
+    fn tick(&mut self) {}
+
+    fn speed_switch(&mut self) -> bool {
+        false
+    }
+}
+
+fn run<B: Bus>(mut cpu: CPU<B>) -> Duration {
+    let start = Instant::now();
+
+    for _ in 0..INSTRUCTIONS {
+        cpu.tick();
+    }
+
+    black_box(&cpu);
+    start.elapsed()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let rate = INSTRUCTIONS as f64 / elapsed.as_secs_f64() / 1_000_000.0;
+
+    println!("{name:<26} {elapsed:>10.2?} ({rate:.1}M instructions/s)");
+}
+
+fn main() {
+    let owned = run(CPU::new(FlatBus::new()));
+
+    let shared: Rc<RefCell<dyn Bus>> = Rc::new(RefCell::new(FlatBus::new()));
+    let dynamic = run(CPU::new(shared));
+
+    report("CPU<FlatBus>", owned);
+    report("CPU<Rc<RefCell<dyn Bus>>>", dynamic);
+    println!(
+        "Static dispatch speedup: {:.2}x",
+        dynamic.as_secs_f64() / owned.as_secs_f64()
+    );
+}
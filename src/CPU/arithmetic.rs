@@ -1,15 +1,16 @@
 use super::CPU;
 use super::utils::*;
+use crate::bus::Bus;
 use paste::paste;
 
 macro_rules! inc_8_bit {
     ($reg:ident) => {
         paste! {
             pub(super) fn [<inc_$reg>](&mut self) {
-                self.set_half_carry_add(self.registers.$reg, self.registers.$reg + 1);
+                self.set_half_carry_add(self.registers.$reg, 1);
                 self.set_sub_flag(false);
 
-                self.registers.$reg += 1;
+                self.registers.$reg = self.registers.$reg.wrapping_add(1);
                 self.set_zero_flag(self.registers.$reg);
             }
         }
@@ -92,7 +93,7 @@ macro_rules! subtract_register_with_carry {
     };
 }
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     pub(super) fn binary_coded_decimal(&mut self) {
         let sub_flag_set = self.registers.f.sub;
         let half_carry_flag_set = self.registers.f.half_carry;
@@ -100,26 +101,24 @@ impl CPU {
 
         let mut adjustment = 0;
 
-        let should_add_carry = carry_flag_set || self.registers.a > 0x99;
+        // After a subtraction the flags alone say which digits borrowed, and carry never changes
+        let should_add_carry = carry_flag_set || (!sub_flag_set && self.registers.a > 0x99);
 
-        let should_add_six_to_adjustment = (sub_flag_set && half_carry_flag_set)
-            || (!sub_flag_set && (half_carry_flag_set || self.registers.a & 0xf > 0x9));
-
-        let should_add_sixty_to_adjustment =
-            (sub_flag_set && carry_flag_set) || (!sub_flag_set && should_add_carry);
+        let should_add_six_to_adjustment =
+            half_carry_flag_set || (!sub_flag_set && self.registers.a & 0xf > 0x9);
 
         if should_add_six_to_adjustment {
             adjustment += 0x6;
         }
 
-        if should_add_sixty_to_adjustment {
+        if should_add_carry {
             adjustment += 0x60;
         }
 
         if sub_flag_set {
-            self.registers.a -= adjustment;
+            self.registers.a = self.registers.a.wrapping_sub(adjustment);
         } else {
-            self.registers.a += adjustment;
+            self.registers.a = self.registers.a.wrapping_add(adjustment);
         }
 
         self.registers.f.zero = self.registers.a == 0;
@@ -245,16 +244,30 @@ impl CPU {
     add_register_with_carry!(h);
     add_register_with_carry!(l);
 
+    fn add_hl(&mut self, value: u16) {
+        let hl = self.registers.hl();
+
+        self.registers.f.carry = carry_occurred_16(hl, value);
+        self.registers.f.half_carry = half_carry_occurred_16(hl, value);
+        self.registers.f.sub = false;
+
+        self.registers.set_hl(hl.wrapping_add(value));
+    }
+
     pub(super) fn add_hl_bc(&mut self) {
-        let half_carry = half_carry_occurred_16(self.registers.bc(), self.registers.hl());
-        let carry = carry_occurred_16(self.registers.bc(), self.registers.hl());
+        self.add_hl(self.registers.bc());
+    }
 
-        self.registers
-            .set_hl(self.registers.bc() + self.registers.hl());
+    pub(super) fn add_hl_de(&mut self) {
+        self.add_hl(self.registers.de());
+    }
 
-        self.registers.f.carry = carry;
-        self.registers.f.half_carry = half_carry;
-        self.registers.f.sub = false;
+    pub(super) fn add_hl_hl(&mut self) {
+        self.add_hl(self.registers.hl());
+    }
+
+    pub(super) fn add_hl_sp(&mut self) {
+        self.add_hl(self.registers.sp);
     }
 
     fn _add_imm_a(&mut self, with_carry: bool) {
@@ -371,3 +384,73 @@ impl CPU {
         self._sub_imm_a(true);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::cpu::test_helpers::make_cpu;
+
+    #[test]
+    fn test_daa_after_addition() {
+        let mut cpu = make_cpu();
+        cpu.registers.a = 0x15u8.wrapping_add(0x27); // 0x3C
+        cpu.registers.f.half_carry = false;
+
+        cpu.binary_coded_decimal();
+
+        assert_eq!(cpu.registers.a, 0x42);
+        assert!(!cpu.registers.f.carry);
+        assert!(!cpu.registers.f.zero);
+    }
+
+    #[test]
+    fn test_daa_after_addition_with_carry_out() {
+        let mut cpu = make_cpu();
+        cpu.registers.a = 0x99u8.wrapping_add(0x01); // 0x9A
+        cpu.registers.f.half_carry = false;
+
+        cpu.binary_coded_decimal();
+
+        assert_eq!(cpu.registers.a, 0x00);
+        assert!(cpu.registers.f.carry);
+        assert!(cpu.registers.f.zero);
+    }
+
+    #[test]
+    fn test_daa_after_subtraction() {
+        let mut cpu = make_cpu();
+        cpu.registers.a = 0x42u8.wrapping_sub(0x15); // 0x2D, borrowed from the low digit
+        cpu.registers.f.sub = true;
+        cpu.registers.f.half_carry = true;
+
+        cpu.binary_coded_decimal();
+
+        assert_eq!(cpu.registers.a, 0x27);
+        assert!(!cpu.registers.f.carry);
+        assert!(cpu.registers.f.sub);
+    }
+
+    #[test]
+    fn test_daa_after_subtraction_keeps_carry_clear() {
+        let mut cpu = make_cpu();
+        cpu.registers.a = 0xA0; // Not reachable from BCD, but must not set carry
+        cpu.registers.f.sub = true;
+
+        cpu.binary_coded_decimal();
+
+        assert_eq!(cpu.registers.a, 0xA0);
+        assert!(!cpu.registers.f.carry);
+    }
+
+    #[test]
+    fn test_daa_after_subtraction_with_borrow() {
+        let mut cpu = make_cpu();
+        cpu.registers.a = 0x10u8.wrapping_sub(0x20); // 0xF0
+        cpu.registers.f.sub = true;
+        cpu.registers.f.carry = true;
+
+        cpu.binary_coded_decimal();
+
+        assert_eq!(cpu.registers.a, 0x90);
+        assert!(cpu.registers.f.carry);
+    }
+}
@@ -0,0 +1,1071 @@
+use std::{collections::HashMap, error::Error, fmt, ops::RangeInclusive};
+
+use crate::cartridge::{NINTENDO_LOGO, global_checksum, header_checksum};
+
+use super::instructions::{Condition, INSTRUCTIONS, Instruction, Operand, PREFIXED_INSTRUCTIONS};
+
+/*
+    A small assembler for RGBDS style source, meant for writing test programs and fixtures.
+    It supports labels (including .local ones), every gbz80 instruction, SECTIONs at fixed addresses,
+    constants via EQU, and the db / dw / ds directives. Expressions are numbers ($hex, %binary or
+    decimal) and symbols, added or subtracted.
+
+    Instructions are matched against the same tables the CPU and disassembler use,
+    so anything the disassembler prints assembles back to the same bytes.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: Option<usize>, // 1-based, None for errors that aren't tied to a line (e.g. overlaps)
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl Error for AssembleError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionType {
+    Rom0,
+    Romx,
+    Vram,
+    Sram,
+    Wram0,
+    Wramx,
+    Oam,
+    Hram,
+}
+
+impl SectionType {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "ROM0" => Some(SectionType::Rom0),
+            "ROMX" => Some(SectionType::Romx),
+            "VRAM" => Some(SectionType::Vram),
+            "SRAM" => Some(SectionType::Sram),
+            "WRAM0" => Some(SectionType::Wram0),
+            "WRAMX" => Some(SectionType::Wramx),
+            "OAM" => Some(SectionType::Oam),
+            "HRAM" => Some(SectionType::Hram),
+            _ => None,
+        }
+    }
+
+    fn range(self) -> RangeInclusive<u16> {
+        match self {
+            SectionType::Rom0 => 0x0000..=0x3FFF,
+            SectionType::Romx => 0x4000..=0x7FFF,
+            SectionType::Vram => 0x8000..=0x9FFF,
+            SectionType::Sram => 0xA000..=0xBFFF,
+            SectionType::Wram0 => 0xC000..=0xCFFF,
+            SectionType::Wramx => 0xD000..=0xDFFF,
+            SectionType::Oam => 0xFE00..=0xFE9F,
+            SectionType::Hram => 0xFF80..=0xFFFE,
+        }
+    }
+
+    // ROMX is limited by what MBC5, which rom() uses for banked images, can map
+    fn banks(self) -> RangeInclusive<i64> {
+        match self {
+            SectionType::Romx => 1..=511,
+            SectionType::Vram => 0..=1,
+            SectionType::Sram => 0..=15,
+            SectionType::Wramx => 1..=7,
+            _ => 0..=0,
+        }
+    }
+}
+
+pub struct Section {
+    pub name: String,
+    pub section_type: SectionType,
+    pub address: u16,
+    pub bank: u16, // Only meaningful for ROMX, where it defaults to 1
+    pub bytes: Vec<u8>,
+}
+
+pub struct Assembly {
+    pub sections: Vec<Section>,
+    symbols: HashMap<String, i64>,
+}
+
+const ENTRY_POINT: u16 = 0x0100;
+const HEADER: RangeInclusive<usize> = 0x0104..=0x014F;
+const BANK_SIZE: usize = 0x4000;
+
+impl Assembly {
+    // Address of a label, or value of an EQU constant
+    pub fn symbol(&self, name: &str) -> Option<u16> {
+        self.symbols.get(name).map(|value| *value as u16)
+    }
+
+    /*
+        Builds a ROM image from the ROM0 / ROMX sections, with a header the boot ROM accepts.
+        Unless a section provides one, the entry point jumps to $0150, where code usually starts.
+        Images larger than 32 KiB are marked as MBC5 so every bank is reachable.
+    */
+    pub fn rom(&self) -> Result<Vec<u8>, AssembleError> {
+        let placed = |section: &Section| match section.section_type {
+            SectionType::Rom0 => Some(section.address as usize),
+            SectionType::Romx => {
+                Some(section.bank as usize * BANK_SIZE + section.address as usize - BANK_SIZE)
+            }
+            _ => None,
+        };
+
+        let banks = self
+            .sections
+            .iter()
+            .filter_map(|section| Some(placed(section)? + section.bytes.len()))
+            .max()
+            .unwrap_or(0)
+            .div_ceil(BANK_SIZE)
+            .max(2)
+            .next_power_of_two();
+
+        let mut rom = vec![0; banks * BANK_SIZE];
+        let mut used = vec![false; rom.len()];
+
+        for section in &self.sections {
+            let Some(offset) = placed(section) else {
+                continue;
+            };
+
+            for (i, byte) in section.bytes.iter().enumerate() {
+                if HEADER.contains(&(offset + i)) {
+                    return Err(error(
+                        None,
+                        format!("Section \"{}\" overlaps the cartridge header", section.name),
+                    ));
+                }
+
+                if used[offset + i] {
+                    return Err(error(
+                        None,
+                        format!("Section \"{}\" overlaps another section", section.name),
+                    ));
+                }
+
+                rom[offset + i] = *byte;
+                used[offset + i] = true;
+            }
+        }
+
+        if !used[ENTRY_POINT as usize] {
+            rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]); // nop, jp $0150
+        }
+
+        rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+        rom[0x147] = if banks > 2 { 0x19 } else { 0x00 }; // MBC5 or ROM only
+        rom[0x148] = (banks / 2).trailing_zeros() as u8;
+
+        rom[0x14D] = header_checksum(&rom);
+        let global_checksum = global_checksum(&rom);
+        rom[0x14E..0x150].copy_from_slice(&global_checksum.to_be_bytes());
+
+        Ok(rom)
+    }
+}
+
+fn error(line: Option<usize>, message: impl Into<String>) -> AssembleError {
+    AssembleError {
+        line,
+        message: message.into(),
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Term {
+    Number(i64),
+    Symbol(String),
+}
+
+// Terms that are added together, each of which may be negated
+#[derive(Clone, Debug)]
+struct Expr {
+    terms: Vec<(bool, Term)>,
+}
+
+impl Expr {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Expected an expression".to_string());
+        }
+
+        let mut terms = Vec::new();
+        let mut negative = false;
+        let mut term = String::new();
+
+        let mut push = |term: &mut String, negative: bool| -> Result<(), String> {
+            let text = term.trim();
+            if text.is_empty() {
+                return Err("Expected a term".to_string());
+            }
+
+            terms.push((negative, parse_term(text)?));
+            term.clear();
+            Ok(())
+        };
+
+        for (i, c) in text.char_indices() {
+            match c {
+                '+' | '-' if term.trim().is_empty() && i == 0 => negative = c == '-',
+                '+' | '-' => {
+                    push(&mut term, negative)?;
+                    negative = c == '-';
+                }
+                _ => term.push(c),
+            }
+        }
+        push(&mut term, negative)?;
+
+        Ok(Self { terms })
+    }
+
+    fn eval(&self, symbols: &HashMap<String, i64>, scope: &str) -> Result<i64, String> {
+        self.terms.iter().try_fold(0, |total, (negative, term)| {
+            let value = match term {
+                Term::Number(value) => *value,
+                Term::Symbol(name) => {
+                    let name = qualify(name, scope);
+                    *symbols
+                        .get(&name)
+                        .ok_or_else(|| format!("Unknown symbol {name}"))?
+                }
+            };
+
+            Ok(if *negative {
+                total - value
+            } else {
+                total + value
+            })
+        })
+    }
+}
+
+fn parse_term(text: &str) -> Result<Term, String> {
+    let number = |digits: &str, radix| {
+        i64::from_str_radix(&digits.replace('_', ""), radix)
+            .map(Term::Number)
+            .map_err(|_| format!("Invalid number {text}"))
+    };
+
+    if let Some(digits) = text.strip_prefix('$') {
+        number(digits, 16)
+    } else if let Some(digits) = text.strip_prefix('%') {
+        number(digits, 2)
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        number(text, 10)
+    } else if is_identifier(text) {
+        Ok(Term::Symbol(text.to_string()))
+    } else {
+        Err(format!("Invalid expression {text}"))
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+// Local labels (".loop") belong to the global label they follow
+fn qualify(name: &str, scope: &str) -> String {
+    if name.starts_with('.') {
+        format!("{scope}{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum ParsedOperand {
+    Keyword(String),  // Register or condition, e.g. "a", "hl", "nz"
+    Indirect(String), // Register indirection, e.g. "hl+" for [hl+]
+    IndirectExpr(Expr),
+    SPOffset(Expr), // sp + e8
+    Expr(Expr),
+}
+
+const KEYWORDS: &[&str] = &[
+    "a", "b", "c", "d", "e", "h", "l", "af", "bc", "de", "hl", "sp", "nz", "z", "nc",
+];
+
+fn parse_operand(text: &str) -> Result<ParsedOperand, String> {
+    let lower = text.trim().to_ascii_lowercase();
+    let compact: String = lower.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if KEYWORDS.contains(&compact.as_str()) {
+        return Ok(ParsedOperand::Keyword(compact));
+    }
+
+    if let Some(inner) = compact
+        .strip_prefix('[')
+        .and_then(|inner| inner.strip_suffix(']'))
+    {
+        let register = match inner {
+            "bc" | "de" | "hl" | "c" | "hl+" | "hl-" => Some(inner),
+            "hli" => Some("hl+"),
+            "hld" => Some("hl-"),
+            "$ff00+c" => Some("c"),
+            _ => None,
+        };
+
+        if let Some(register) = register {
+            return Ok(ParsedOperand::Indirect(register.to_string()));
+        }
+
+        let inner = text.trim();
+        return Expr::parse(&inner[1..inner.len() - 1]).map(ParsedOperand::IndirectExpr);
+    }
+
+    if let Some(offset) = compact.strip_prefix("sp")
+        && offset.starts_with(['+', '-'])
+    {
+        return Expr::parse(offset).map(ParsedOperand::SPOffset);
+    }
+
+    Expr::parse(text).map(ParsedOperand::Expr)
+}
+
+fn operand_keyword(operand: Operand) -> Option<&'static str> {
+    Some(match operand {
+        Operand::A => "a",
+        Operand::B => "b",
+        Operand::C | Operand::Cond(Condition::Carry) => "c",
+        Operand::D => "d",
+        Operand::E => "e",
+        Operand::H => "h",
+        Operand::L => "l",
+        Operand::AF => "af",
+        Operand::BC => "bc",
+        Operand::DE => "de",
+        Operand::HL => "hl",
+        Operand::SP => "sp",
+        Operand::Cond(Condition::NotZero) => "nz",
+        Operand::Cond(Condition::Zero) => "z",
+        Operand::Cond(Condition::NotCarry) => "nc",
+        _ => return None,
+    })
+}
+
+fn operand_indirect(operand: Operand) -> Option<&'static str> {
+    Some(match operand {
+        Operand::IndBC => "bc",
+        Operand::IndDE => "de",
+        Operand::IndHL => "hl",
+        Operand::IndHLInc => "hl+",
+        Operand::IndHLDec => "hl-",
+        Operand::IndHighC => "c",
+        _ => return None,
+    })
+}
+
+// Symbols and scope as of the line being matched, for operands that select the opcode (RST, BIT)
+struct Context<'a> {
+    symbols: &'a HashMap<String, i64>,
+    scope: &'a str,
+}
+
+fn operand_fits(
+    parsed: &ParsedOperand,
+    operand: Operand,
+    context: &Context,
+) -> Result<bool, String> {
+    let constant = |expr: &Expr| {
+        expr.eval(context.symbols, context.scope)
+            .map_err(|why| format!("{why}, RST targets and bit indices must be defined first"))
+    };
+
+    Ok(match (parsed, operand) {
+        (ParsedOperand::Keyword(keyword), _) => operand_keyword(operand) == Some(keyword.as_str()),
+        (ParsedOperand::Indirect(register), _) => {
+            operand_indirect(operand) == Some(register.as_str())
+        }
+        (ParsedOperand::IndirectExpr(_), Operand::IndImm16 | Operand::IndHighImm8) => true,
+        (ParsedOperand::SPOffset(_), Operand::SPOffset8) => true,
+        (
+            ParsedOperand::Expr(_),
+            Operand::Imm8 | Operand::Imm16 | Operand::SignedImm8 | Operand::Offset8,
+        ) => true,
+        (ParsedOperand::Expr(expr), Operand::Vector(vector)) => constant(expr)? == vector as i64,
+        (ParsedOperand::Expr(expr), Operand::Bit(bit)) => constant(expr)? == bit as i64,
+        _ => false,
+    })
+}
+
+// Opcode bytes and metadata of the instruction matching a mnemonic and its operands
+type Encoding = (&'static [u8], &'static Instruction);
+
+const OPCODES: [[u8; 1]; 256] = {
+    let mut opcodes = [[0; 1]; 256];
+    let mut i = 0;
+    while i < 256 {
+        opcodes[i][0] = i as u8;
+        i += 1;
+    }
+    opcodes
+};
+
+const PREFIXED_OPCODES: [[u8; 2]; 256] = {
+    let mut opcodes = [[0xCB, 0]; 256];
+    let mut i = 0;
+    while i < 256 {
+        opcodes[i][1] = i as u8;
+        i += 1;
+    }
+    opcodes
+};
+
+fn find_instruction(
+    mnemonic: &str,
+    operands: &[ParsedOperand],
+    context: &Context,
+) -> Result<Option<Encoding>, String> {
+    let table = INSTRUCTIONS
+        .iter()
+        .zip(OPCODES.iter().map(|opcode| opcode.as_slice()))
+        .chain(
+            PREFIXED_INSTRUCTIONS
+                .iter()
+                .zip(PREFIXED_OPCODES.iter().map(|opcode| opcode.as_slice())),
+        );
+
+    for (instruction, opcode) in table {
+        if instruction.is_illegal()
+            || instruction.mnemonic != mnemonic
+            || instruction.operands.len() != operands.len()
+        {
+            continue;
+        }
+
+        let mut fits = true;
+        for (parsed, operand) in operands.iter().zip(instruction.operands) {
+            fits &= operand_fits(parsed, *operand, context)?;
+        }
+
+        if fits {
+            return Ok(Some((opcode, instruction)));
+        }
+    }
+
+    Ok(None)
+}
+
+// Also accepts the shorthands RGBDS does: "cp b" for "cp a, b" and "ld [c], a" for "ldh [c], a"
+fn select_instruction(
+    mnemonic: &str,
+    operands: Vec<ParsedOperand>,
+    context: &Context,
+) -> Result<(Encoding, Vec<ParsedOperand>), String> {
+    if let Some(encoding) = find_instruction(mnemonic, &operands, context)? {
+        return Ok((encoding, operands));
+    }
+
+    const ACCUMULATOR_OPS: &[&str] = &["add", "adc", "sub", "sbc", "and", "xor", "or", "cp"];
+
+    if ACCUMULATOR_OPS.contains(&mnemonic) && operands.len() == 1 {
+        let operands = vec![ParsedOperand::Keyword("a".to_string()), operands[0].clone()];
+
+        if let Some(encoding) = find_instruction(mnemonic, &operands, context)? {
+            return Ok((encoding, operands));
+        }
+    }
+
+    if mnemonic == "ld"
+        && let Some(encoding) = find_instruction("ldh", &operands, context)?
+    {
+        return Ok((encoding, operands));
+    }
+
+    Err(format!(
+        "No instruction matches {mnemonic} with these operands"
+    ))
+}
+
+enum DataItem {
+    Expr(Expr),
+    Bytes(Vec<u8>),
+}
+
+enum Statement {
+    Instruction(Encoding, Vec<ParsedOperand>),
+    Bytes(Vec<DataItem>),
+    Words(Vec<Expr>),
+    Space(usize, u8),
+}
+
+struct Item {
+    line: usize,
+    scope: String,
+    section: usize,
+    address: u16,
+    statement: Statement,
+}
+
+// Splits on commas that aren't inside quotes or brackets
+fn split_arguments(text: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' if !quoted => depth += 1,
+            ']' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                arguments.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let last = text[start..].trim();
+    if !last.is_empty() || !arguments.is_empty() {
+        arguments.push(last);
+    }
+
+    arguments
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+// Parses `TYPE[$address]` or `BANK[n]`
+fn bracketed<'a>(text: &'a str, what: &str) -> Result<(&'a str, &'a str), String> {
+    let (name, rest) = text
+        .split_once('[')
+        .ok_or_else(|| format!("Expected {what}[...]"))?;
+    let value = rest
+        .strip_suffix(']')
+        .ok_or_else(|| format!("Expected {what}[...]"))?;
+
+    Ok((name.trim(), value))
+}
+
+struct Assembler {
+    sections: Vec<Section>,
+    symbols: HashMap<String, i64>,
+    items: Vec<Item>,
+    scope: String,
+    // Offset into the current section, kept separately so sizes can be laid out before encoding
+    offsets: Vec<usize>,
+}
+
+impl Assembler {
+    fn constant(&self, text: &str) -> Result<i64, String> {
+        Expr::parse(text)?.eval(&self.symbols, &self.scope)
+    }
+
+    fn define(&mut self, name: &str, value: i64) -> Result<(), String> {
+        let name = if name.starts_with('.') {
+            if self.scope.is_empty() {
+                return Err(format!("Local label {name} has no global label before it"));
+            }
+            qualify(name, &self.scope)
+        } else {
+            name.to_string()
+        };
+
+        if self.symbols.insert(name.clone(), value).is_some() {
+            return Err(format!("{name} is defined more than once"));
+        }
+
+        Ok(())
+    }
+
+    fn address(&self) -> Result<u16, String> {
+        let section = self
+            .sections
+            .last()
+            .ok_or("Code and labels must be inside a SECTION")?;
+
+        Ok(section
+            .address
+            .wrapping_add(self.offsets[self.sections.len() - 1] as u16))
+    }
+
+    fn section(&mut self, arguments: &str) -> Result<(), String> {
+        let arguments = split_arguments(arguments);
+
+        let name = arguments
+            .first()
+            .and_then(|name| name.strip_prefix('"'))
+            .and_then(|name| name.strip_suffix('"'))
+            .ok_or("Expected a quoted section name")?;
+
+        let (section_type, address) = bracketed(arguments.get(1).unwrap_or(&""), "TYPE")?;
+        let section_type = SectionType::parse(section_type)
+            .ok_or_else(|| format!("Unknown section type {section_type}"))?;
+        let address = self.constant(address)?;
+
+        if !(0..=0xFFFF).contains(&address) || !section_type.range().contains(&(address as u16)) {
+            return Err(format!("${address:04X} is outside of {section_type:?}"));
+        }
+
+        let bank = match arguments.get(2) {
+            Some(bank) => {
+                let (keyword, bank) = bracketed(bank, "BANK")?;
+                if !keyword.eq_ignore_ascii_case("bank") {
+                    return Err(format!("Expected BANK[...], found {keyword}"));
+                }
+                let bank = self.constant(bank)?;
+                if !section_type.banks().contains(&bank) {
+                    return Err(format!("Bank {bank} is outside of {section_type:?}"));
+                }
+                bank as u16
+            }
+            None if section_type == SectionType::Romx => 1,
+            None => 0,
+        };
+
+        self.sections.push(Section {
+            name: name.to_string(),
+            section_type,
+            address: address as u16,
+            bank,
+            bytes: Vec::new(),
+        });
+        self.offsets.push(0);
+
+        Ok(())
+    }
+
+    fn push(&mut self, line: usize, size: usize, statement: Statement) -> Result<(), String> {
+        let address = self.address()?;
+        let section = self.sections.len() - 1;
+
+        self.items.push(Item {
+            line,
+            scope: self.scope.clone(),
+            section,
+            address,
+            statement,
+        });
+        self.offsets[section] += size;
+
+        let end = self.sections[section].address as usize + self.offsets[section];
+        if end > *self.sections[section].section_type.range().end() as usize + 1 {
+            return Err(format!(
+                "Section \"{}\" doesn't fit in its memory region",
+                self.sections[section].name
+            ));
+        }
+
+        Ok(())
+    }
+
+    // First pass: defines labels and picks an encoding (and so a size) for every statement
+    fn line(&mut self, number: usize, text: &str) -> Result<(), String> {
+        let mut text = strip_comment(text).trim();
+
+        // Labels, which may be followed by code on the same line
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_identifier(label) {
+                break;
+            }
+
+            if !label.starts_with('.') {
+                self.scope = label.to_string();
+            }
+            self.define(label, self.address()? as i64)?;
+
+            text = rest.trim_start_matches(':').trim();
+        }
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let (keyword, arguments) = text
+            .split_once(char::is_whitespace)
+            .map(|(keyword, arguments)| (keyword, arguments.trim()))
+            .unwrap_or((text, ""));
+        let keyword = keyword.to_ascii_lowercase();
+
+        // NAME EQU value, or DEF NAME EQU value
+        let definition = if keyword == "def" {
+            arguments.split_once(char::is_whitespace)
+        } else {
+            Some((text.split_whitespace().next().unwrap_or(""), arguments))
+        };
+        if let Some((name, rest)) = definition
+            && let Some((equ, value)) = rest.trim().split_once(char::is_whitespace)
+            && equ.eq_ignore_ascii_case("equ")
+        {
+            let value = self.constant(value)?;
+            return self.define(name.trim(), value);
+        }
+
+        match keyword.as_str() {
+            "section" => self.section(arguments),
+            "db" => {
+                let mut size = 0;
+                let mut items = Vec::new();
+
+                for argument in split_arguments(arguments) {
+                    if let Some(string) = argument
+                        .strip_prefix('"')
+                        .and_then(|string| string.strip_suffix('"'))
+                    {
+                        size += string.len();
+                        items.push(DataItem::Bytes(string.as_bytes().to_vec()));
+                    } else {
+                        size += 1;
+                        items.push(DataItem::Expr(Expr::parse(argument)?));
+                    }
+                }
+
+                self.push(number, size, Statement::Bytes(items))
+            }
+            "dw" => {
+                let words = split_arguments(arguments)
+                    .into_iter()
+                    .map(Expr::parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.push(number, words.len() * 2, Statement::Words(words))
+            }
+            "ds" => {
+                let arguments = split_arguments(arguments);
+                let count = self.constant(arguments.first().unwrap_or(&""))?;
+                let fill = match arguments.get(1) {
+                    Some(fill) => self.constant(fill)? as u8,
+                    None => 0,
+                };
+
+                // Anything larger can't fit in a section, and would overflow the offsets
+                if !(0..=0x10000).contains(&count) {
+                    return Err(format!("Can't reserve {count} bytes"));
+                }
+
+                self.push(
+                    number,
+                    count as usize,
+                    Statement::Space(count as usize, fill),
+                )
+            }
+            mnemonic => {
+                let operands = split_arguments(arguments)
+                    .into_iter()
+                    .map(parse_operand)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let context = Context {
+                    symbols: &self.symbols,
+                    scope: &self.scope,
+                };
+                let (encoding, operands) = select_instruction(mnemonic, operands, &context)?;
+
+                self.push(
+                    number,
+                    encoding.1.length as usize,
+                    Statement::Instruction(encoding, operands),
+                )
+            }
+        }
+    }
+
+    // Second pass: every label is known, so expressions can be evaluated
+    fn encode(&self, item: &Item) -> Result<Vec<u8>, String> {
+        let eval = |expr: &Expr| expr.eval(&self.symbols, &item.scope);
+
+        let fit = |value: i64, range: RangeInclusive<i64>| {
+            if range.contains(&value) {
+                Ok(value)
+            } else {
+                Err(format!("{value} doesn't fit in {range:?}"))
+            }
+        };
+
+        let mut bytes = Vec::new();
+
+        match &item.statement {
+            Statement::Bytes(items) => {
+                for data in items {
+                    match data {
+                        DataItem::Expr(expr) => bytes.push(fit(eval(expr)?, -128..=255)? as u8),
+                        DataItem::Bytes(string) => bytes.extend(string),
+                    }
+                }
+            }
+            Statement::Words(words) => {
+                for word in words {
+                    let value = fit(eval(word)?, -32768..=65535)? as u16;
+                    bytes.extend(value.to_le_bytes());
+                }
+            }
+            Statement::Space(count, fill) => bytes.resize(*count, *fill),
+            Statement::Instruction((opcode, instruction), operands) => {
+                bytes.extend(*opcode);
+
+                for (parsed, operand) in operands.iter().zip(instruction.operands) {
+                    let expr = match parsed {
+                        ParsedOperand::Expr(expr)
+                        | ParsedOperand::IndirectExpr(expr)
+                        | ParsedOperand::SPOffset(expr) => expr,
+                        _ => continue,
+                    };
+
+                    match operand {
+                        Operand::Imm8 => bytes.push(fit(eval(expr)?, -128..=255)? as u8),
+                        Operand::SignedImm8 | Operand::SPOffset8 => {
+                            bytes.push(fit(eval(expr)?, -128..=127)? as u8)
+                        }
+                        Operand::Imm16 | Operand::IndImm16 => {
+                            let value = fit(eval(expr)?, -32768..=65535)? as u16;
+                            bytes.extend(value.to_le_bytes());
+                        }
+                        Operand::IndHighImm8 => {
+                            let value = eval(expr)?;
+                            if !(0xFF00..=0xFFFF).contains(&value) && !(0..=0xFF).contains(&value) {
+                                return Err(format!("${value:04X} is outside of $FF00-$FFFF"));
+                            }
+                            bytes.push(value as u8);
+                        }
+                        Operand::Offset8 => {
+                            let next = item.address as i64 + instruction.length as i64;
+                            let offset = eval(expr)? - next;
+                            if !(-128..=127).contains(&offset) {
+                                return Err(format!("Jump target is {offset} bytes away"));
+                            }
+                            bytes.push(offset as u8);
+                        }
+                        _ => {}
+                    }
+                }
+
+                // STOP's padding byte
+                bytes.resize(instruction.length as usize, 0);
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Assembly, AssembleError> {
+    let mut assembler = Assembler {
+        sections: Vec::new(),
+        symbols: HashMap::new(),
+        items: Vec::new(),
+        scope: String::new(),
+        offsets: Vec::new(),
+    };
+
+    for (i, text) in source.lines().enumerate() {
+        assembler
+            .line(i + 1, text)
+            .map_err(|why| error(Some(i + 1), why))?;
+    }
+
+    for item in &assembler.items {
+        let bytes = assembler
+            .encode(item)
+            .map_err(|why| error(Some(item.line), why))?;
+
+        assembler.sections[item.section].bytes.extend(bytes);
+    }
+
+    Ok(Assembly {
+        sections: assembler.sections,
+        symbols: assembler.symbols,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::disassembler::disassemble_instruction;
+    use crate::{Emulator, IllegalOpcode, Model};
+
+    fn assemble_at_150(code: &str) -> Vec<u8> {
+        let source = format!("SECTION \"test\", ROM0[$0150]\n{code}");
+        let assembly = assemble(&source).unwrap();
+
+        assembly.sections[0].bytes.clone()
+    }
+
+    #[test]
+    fn test_disassembly_round_trips() {
+        let legal = |bytes: &[u8]| {
+            let instruction = disassemble_instruction(bytes, 0x0150).unwrap();
+            instruction.instruction.map(|_| instruction)
+        };
+
+        // STOP's second byte isn't part of its text, so it has to be the zero the assembler pads with
+        let unprefixed = (0..=0xFF)
+            .filter(|opcode| *opcode != 0xCB)
+            .map(|opcode| vec![opcode, 0x00, 0x12]);
+        let prefixed = (0..=0xFF).map(|opcode| vec![0xCB, opcode]);
+
+        for bytes in unprefixed.chain(prefixed) {
+            let Some(instruction) = legal(&bytes) else {
+                continue;
+            };
+
+            assert_eq!(
+                assemble_at_150(&instruction.text),
+                instruction.bytes,
+                "{}",
+                instruction.text
+            );
+        }
+    }
+
+    #[test]
+    fn test_labels_and_directives() {
+        let source = "
+            DEF rLCDC EQU $FF40
+            SECTION \"main\", ROM0[$0150]
+            Main:
+                ld a, [rLCDC]   ; comment
+                cp 3
+            .loop:
+                jr nz, .loop
+                jp Main
+            Data: db 1, \"hi\", -1
+                dw Data, $1234
+                ds 2, $FF
+        ";
+
+        let assembly = assemble(source).unwrap();
+
+        assert_eq!(assembly.symbol("Main"), Some(0x0150));
+        assert_eq!(assembly.symbol("Main.loop"), Some(0x0155));
+        assert_eq!(
+            assembly.sections[0].bytes,
+            [
+                0xFA, 0x40, 0xFF, // ld a, [$FF40]
+                0xFE, 0x03, // cp a, 3
+                0x20, 0xFE, // jr nz, .loop
+                0xC3, 0x50, 0x01, // jp Main
+                0x01, b'h', b'i', 0xFF, // db
+                0x5A, 0x01, 0x34, 0x12, // dw
+                0xFF, 0xFF, // ds
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_report_lines() {
+        let source = "SECTION \"main\", ROM0[$0150]\n  ld a, [de+]";
+
+        assert_eq!(assemble(source).err().unwrap().line, Some(2));
+
+        let source = "SECTION \"main\", ROM0[$0150]\n  jr Far\n  ds 200\nFar:";
+
+        assert_eq!(assemble(source).err().unwrap().line, Some(2));
+    }
+
+    #[test]
+    fn test_rejects_bad_space_counts() {
+        let error = assemble("SECTION \"a\", ROM0[$0150]\n ds -1")
+            .err()
+            .unwrap();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.message, "Can't reserve -1 bytes");
+
+        let error = assemble("SECTION \"a\", ROM0[$0150]\n ds $FFFFFFFF")
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Can't reserve 4294967295 bytes");
+
+        let error = assemble("SECTION \"a\", ROM0[$0150]\n ds $4000")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.message,
+            "Section \"a\" doesn't fit in its memory region"
+        );
+    }
+
+    #[test]
+    fn test_rejects_banks_outside_the_section_type() {
+        let error = assemble("SECTION \"a\", ROMX[$4000], BANK[0]")
+            .err()
+            .unwrap();
+        assert_eq!(error.line, Some(1));
+        assert_eq!(error.message, "Bank 0 is outside of Romx");
+
+        let error = assemble("SECTION \"a\", ROMX[$4000], BANK[512]")
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Bank 512 is outside of Romx");
+
+        let error = assemble("SECTION \"a\", ROM0[$0150], BANK[1]")
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Bank 1 is outside of Rom0");
+
+        let assembly = assemble("SECTION \"a\", ROMX[$4000], BANK[511]\n db 1").unwrap();
+        let rom = assembly.rom().unwrap();
+        assert_eq!(rom.len(), 512 * 0x4000);
+        assert_eq!(rom[511 * 0x4000], 1);
+    }
+
+    #[test]
+    fn test_rom_runs_in_emulator() {
+        // Waits for the timer interrupt, then locks up on an illegal opcode in its handler
+        let source = "
+            SECTION \"timer\", ROM0[$0050]
+                jp TimerFired
+
+            SECTION \"main\", ROM0[$0150]
+            Main:
+                ld a, %101        ; Timer enabled, increments every 4 M-cycles
+                ldh [$FF07], a
+                ld a, %100
+                ldh [$FFFF], a    ; Only the timer interrupt
+                xor a
+                ldh [$FF0F], a
+                ei
+            .wait:
+                halt
+                jr .wait
+
+            TimerFired:
+                db $D3
+        ";
+
+        let assembly = assemble(source).unwrap();
+        let rom = assembly.rom().unwrap();
+
+        assert_eq!(rom.len(), 0x8000);
+        assert_eq!(rom[0x14D], 0xE7); // Header checksum of an empty header
+
+        let mut emulator = Emulator::from_rom_bytes(rom, Model::Dmg).unwrap();
+
+        assert_eq!(
+            emulator.execute(),
+            Err(IllegalOpcode {
+                opcode: 0xD3,
+                pc: assembly.symbol("TimerFired").unwrap(),
+            })
+        );
+    }
+}
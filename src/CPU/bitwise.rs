@@ -1,4 +1,5 @@
 use super::CPU;
+use crate::bus::Bus;
 use paste::paste;
 
 macro_rules! register_and {
@@ -46,7 +47,7 @@ macro_rules! register_or {
     };
 }
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     pub(super) fn flip_register_a(&mut self) {
         self.registers.a = !self.registers.a;
 
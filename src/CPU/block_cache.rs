@@ -0,0 +1,392 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    rc::Rc,
+};
+
+use super::CPU;
+use super::instructions::{INSTRUCTIONS, InstructionFn, PREFIXED_INSTRUCTIONS};
+use crate::bus::Bus;
+
+// Longest run of instructions decoded in one go
+const MAX_BLOCK_LENGTH: usize = 64;
+
+// Mnemonics that end straight-line code, either by branching or by changing the run state
+const BLOCK_TERMINATORS: &[&str] = &["jp", "jr", "call", "ret", "reti", "rst", "halt", "stop"];
+
+struct DecodedOp<B: Bus> {
+    pc: u16,
+    fetch_length: u8, // Bytes the opcode itself takes up, 2 for CB-prefixed instructions
+    handler: InstructionFn<B>,
+}
+
+// Derived impls would require B: Copy, though only a function pointer to it is stored
+impl<B: Bus> Clone for DecodedOp<B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B: Bus> Copy for DecodedOp<B> {}
+
+struct Block<B: Bus> {
+    ops: Vec<DecodedOp<B>>,
+    end: u16, // One past the last byte of the last instruction
+}
+
+impl<B: Bus> Block<B> {
+    fn start(&self) -> u16 {
+        self.ops[0].pc
+    }
+
+    fn contains(&self, addr: u16) -> bool {
+        (self.start()..self.end).contains(&addr)
+    }
+
+    /*
+        Decodes instructions from `start` until one that branches, halts or locks up.
+        Blocks also stop at 4 KiB boundaries, since every bank window is aligned to one,
+        so a block never mixes code from two banks.
+    */
+    fn decode(bus: &B, start: u16) -> Self {
+        let mut ops = Vec::new();
+        let mut pc = start;
+
+        loop {
+            let opcode = bus.read(pc);
+
+            let (instruction, op) = match opcode {
+                0xCB => {
+                    let opcode = bus.read(pc.wrapping_add(1));
+                    let op = DecodedOp {
+                        pc,
+                        fetch_length: 2,
+                        handler: CPU::PREFIXED_HANDLERS[opcode as usize],
+                    };
+
+                    (&PREFIXED_INSTRUCTIONS[opcode as usize], op)
+                }
+                _ => {
+                    let op = DecodedOp {
+                        pc,
+                        fetch_length: 1,
+                        handler: CPU::HANDLERS[opcode as usize],
+                    };
+
+                    (&INSTRUCTIONS[opcode as usize], op)
+                }
+            };
+
+            ops.push(op);
+            let next = pc.wrapping_add(instruction.length as u16);
+
+            if instruction.is_illegal()
+                || BLOCK_TERMINATORS.contains(&instruction.mnemonic)
+                || ops.len() == MAX_BLOCK_LENGTH
+                || next & 0xF000 != start & 0xF000
+            {
+                return Self { ops, end: next };
+            }
+
+            pc = next;
+        }
+    }
+}
+
+// Blocks are looked up on every taken branch, so keys get a cheap multiplicative hash instead of SipHash
+#[derive(Default)]
+struct BlockKeyHasher(u64);
+
+impl Hasher for BlockKeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u8(*byte);
+        }
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.write_u32(value as u32);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.0 = (self.0.rotate_left(5) ^ value as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub(super) struct BlockCache<B: Bus> {
+    blocks: HashMap<u32, Rc<Block<B>>, BuildHasherDefault<BlockKeyHasher>>, // Keyed by bank << 16 | start
+    current: Option<(Rc<Block<B>>, usize)>, // Block being executed and the index of the next op
+    code: Vec<bool>, // Addresses that are part of a cached block, in any bank
+}
+
+impl<B: Bus> BlockCache<B> {
+    pub(super) fn new() -> Self {
+        Self {
+            blocks: HashMap::default(),
+            current: None,
+            code: vec![false; 0x10000],
+        }
+    }
+
+    #[inline]
+    fn next_op(&mut self, bus: &B, pc: u16) -> DecodedOp<B> {
+        if let Some((block, index)) = &mut self.current
+            && let Some(op) = block.ops.get(*index)
+            && op.pc == pc
+        {
+            *index += 1;
+            return *op;
+        }
+
+        self.enter_block(bus, pc)
+    }
+
+    // Kept out of line so the per-op path in next_op stays small enough to inline
+    #[inline(never)]
+    fn enter_block(&mut self, bus: &B, pc: u16) -> DecodedOp<B> {
+        let key = (bus.code_bank(pc) as u32) << 16 | pc as u32;
+        let block = match self.blocks.get(&key) {
+            Some(block) => block.clone(),
+            None => {
+                let block = Rc::new(Block::decode(bus, pc));
+
+                for addr in block.start()..block.end {
+                    self.code[addr as usize] = true;
+                }
+
+                self.blocks.insert(key, block.clone());
+                block
+            }
+        };
+
+        let op = block.ops[0];
+        self.current = Some((block, 1));
+
+        op
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.blocks.clear();
+        self.current = None;
+        self.code.fill(false);
+    }
+
+    // Called for every write the CPU makes, so self-modifying code and bank switches are picked up
+    fn invalidate(&mut self, addr: u16) {
+        // Writes to the ROM area go to the MBC, which may have switched the bank we're executing from
+        if addr < 0x8000 {
+            self.current = None;
+        }
+
+        // Echo RAM and the WRAM it mirrors are the same bytes, so code cached under either changed
+        match addr {
+            0xC000..=0xDDFF => self.invalidate_addr(addr + 0x2000),
+            0xE000..=0xFDFF => self.invalidate_addr(addr - 0x2000),
+            _ => {}
+        }
+        self.invalidate_addr(addr);
+    }
+
+    fn invalidate_addr(&mut self, addr: u16) {
+        if !self.code[addr as usize] {
+            return;
+        }
+
+        self.blocks.retain(|_, block| !block.contains(addr));
+        self.current = None;
+
+        // Blocks can overlap, so rebuild the coverage rather than clearing the dropped ranges
+        self.code.fill(false);
+        for block in self.blocks.values() {
+            for addr in block.start()..block.end {
+                self.code[addr as usize] = true;
+            }
+        }
+    }
+}
+
+impl<B: Bus> CPU<B> {
+    /*
+        Executes from decoded blocks instead of fetching and decoding every instruction.
+        Timing, interrupts and results are the same as the plain interpreter. This only saves the
+        opcode reads and table lookups, so it pays off when reads go through an expensive memory map
+        and not on a flat one.
+    */
+    pub fn set_block_cache(&mut self, enabled: bool) {
+        self.block_cache = enabled.then(BlockCache::new);
+    }
+
+    pub(super) fn invalidate_block_cache(&mut self, addr: u16) {
+        if let Some(cache) = &mut self.block_cache {
+            cache.invalidate(addr);
+        }
+    }
+
+    // Same as run_instruction, but with the opcode fetch already decoded
+    pub(super) fn run_cached_instruction(&mut self) {
+        let Some(cache) = &mut self.block_cache else {
+            return self.run_instruction();
+        };
+
+        let pc = self.registers.pc;
+        let op = cache.next_op(&self.bus, pc);
+
+        let start = self.cycles;
+
+        self.cycle();
+        if op.fetch_length == 2 {
+            self.cycle();
+        }
+        self.registers.pc = pc.wrapping_add(op.fetch_length as u16);
+
+        let cycles = (op.handler)(self);
+
+        self.finish_instruction(start, cycles);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::{Bus, SystemBus};
+    use crate::cpu::CPU;
+    use crate::cpu::test_helpers::make_cpu;
+    use crate::joypad::Button;
+    use crate::model::Model;
+
+    /*
+        Flips the INC at $C008 between INC C and INC D on every pass,
+        which only works if writing over cached code invalidates it.
+    */
+    const SELF_MODIFYING_LOOP: &[u8] = &[
+        0x21, 0x08, 0xC0, // ld hl, $C008
+        0x7E, // ld a, [hl]
+        0xEE, 0x18, // xor $18
+        0x77, // ld [hl], a
+        0x00, // nop
+        0x0C, // inc c
+        0x18, 0xF5, // jr $C000
+    ];
+
+    // Same loop, but it reads and writes its code through the echo of $C008
+    const ECHO_MODIFYING_LOOP: &[u8] = &[
+        0x21, 0x08, 0xE0, // ld hl, $E008
+        0x7E, // ld a, [hl]
+        0xEE, 0x18, // xor $18
+        0x77, // ld [hl], a
+        0x00, // nop
+        0x0C, // inc c
+        0x18, 0xF5, // jr $C000
+    ];
+
+    fn load<B: Bus>(cpu: &mut CPU<B>, program: &[u8]) {
+        for (offset, byte) in program.iter().enumerate() {
+            cpu.bus.write(0xC000 + offset as u16, *byte);
+        }
+
+        cpu.registers.pc = 0xC000;
+    }
+
+    fn assert_same_state<B: Bus>(plain: &CPU<B>, cached: &CPU<B>) {
+        assert_eq!(plain.registers.pc, cached.registers.pc);
+        assert_eq!(plain.registers.af(), cached.registers.af());
+        assert_eq!(plain.registers.bc(), cached.registers.bc());
+        assert_eq!(plain.registers.de(), cached.registers.de());
+        assert_eq!(plain.registers.hl(), cached.registers.hl());
+        assert_eq!(plain.cycles, cached.cycles);
+        assert_eq!(plain.peek(0xC008), cached.peek(0xC008));
+    }
+
+    #[test]
+    fn test_block_cache_matches_interpreter() {
+        let mut plain = make_cpu();
+        let mut cached = make_cpu();
+        cached.set_block_cache(true);
+
+        load(&mut plain, SELF_MODIFYING_LOOP);
+        load(&mut cached, SELF_MODIFYING_LOOP);
+
+        for _ in 0..1000 {
+            plain.tick();
+            cached.tick();
+
+            assert_same_state(&plain, &cached);
+            assert_eq!(plain.bus.ticks, cached.bus.ticks);
+        }
+
+        // 1000 instructions reach the INC 143 times, starting with INC D since the first pass flips it
+        assert_eq!(cached.registers.c, 71);
+        assert_eq!(cached.registers.d, 72);
+    }
+
+    #[test]
+    fn test_block_cache_sees_writes_through_echo_ram() {
+        let make_system_cpu = || CPU::new(SystemBus::from_rom(vec![0; 0x8000], Model::Dmg));
+        let mut plain = make_system_cpu();
+        let mut cached = make_system_cpu();
+        cached.set_block_cache(true);
+
+        load(&mut plain, ECHO_MODIFYING_LOOP);
+        load(&mut cached, ECHO_MODIFYING_LOOP);
+
+        for _ in 0..1000 {
+            plain.tick();
+            cached.tick();
+
+            assert_same_state(&plain, &cached);
+        }
+
+        assert_eq!(cached.registers.c, 71);
+        assert_eq!(cached.registers.d, 72);
+    }
+
+    #[test]
+    fn test_bank_switch_outside_the_cpu_drops_blocks() {
+        // MBC1 with 4 banks, bank 1 counts up A and bank 2 counts up B from $4000
+        let mut rom = vec![0; 0x10000];
+        rom[0x147] = 0x01;
+        rom[0x148] = 0x01;
+        for (bank, inc) in [(1, 0x3C), (2, 0x04)] {
+            let start = bank * 0x4000;
+            rom[start..start + 6].copy_from_slice(&[inc, inc, inc, 0xC3, 0x00, 0x40]);
+        }
+
+        let mut cpu = CPU::new(SystemBus::from_rom(rom, Model::Dmg));
+        cpu.set_block_cache(true);
+        cpu.registers.a = 0;
+        cpu.registers.b = 0;
+        cpu.registers.pc = 0x4000;
+
+        cpu.tick();
+        assert_eq!(cpu.registers.a, 1);
+
+        // Switched in the middle of the cached block, e.g. by a debugger
+        cpu.memory_mut().write(0x2000, 0x02);
+        cpu.tick();
+        cpu.tick();
+
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.registers.b, 2);
+        assert_eq!(cpu.registers.pc, 0x4003);
+    }
+
+    #[test]
+    fn test_input_keeps_cached_blocks() {
+        let mut cpu = CPU::new(SystemBus::from_rom(vec![0; 0x8000], Model::Dmg));
+        cpu.set_block_cache(true);
+        cpu.registers.pc = 0x0150;
+
+        cpu.tick();
+        cpu.bus_mut().press_button(Button::A);
+        cpu.bus_mut().take_cartridge_events();
+
+        assert_eq!(cpu.block_cache.as_ref().unwrap().blocks.len(), 1);
+
+        cpu.memory_mut().load_save_data(&[0; 0x2000]);
+
+        assert!(cpu.block_cache.as_ref().unwrap().blocks.is_empty());
+    }
+}
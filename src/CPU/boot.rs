@@ -0,0 +1,160 @@
+use super::CPU;
+use super::registers::FlagRegister;
+use crate::bus::Bus;
+use crate::model::Model;
+
+const TITLE_ADDR: u16 = 0x134;
+const CGB_FLAG_ADDR: u16 = 0x143;
+const NEW_LICENSEE_ADDR: u16 = 0x144;
+const OLD_LICENSEE_ADDR: u16 = 0x14B;
+const HEADER_CHECKSUM_ADDR: u16 = 0x14D;
+
+impl<B: Bus> CPU<B> {
+    /*
+        Leaves the registers as the model's boot ROM would when it hands over to the cartridge.
+        Most models only differ by constants, but the monochrome boot ROMs leave flags from the
+        header checksum, and the CGB boot ROM leaves a hash of the title behind in DMG mode,
+        which it uses to pick a compatibility palette.
+    */
+    pub fn skip_boot_rom(&mut self, model: Model) {
+        let checksum_nonzero = self.peek(HEADER_CHECKSUM_ADDR) != 0;
+
+        self.registers.sp = 0xFFFE;
+        self.registers.pc = 0x0100;
+
+        match model {
+            Model::Dmg0 => {
+                self.registers.a = 0x01;
+                self.registers.f = FlagRegister::from(0x00);
+                self.registers.set_bc(0xFF13);
+                self.registers.set_de(0x00C1);
+                self.registers.set_hl(0x8403);
+            }
+            Model::Dmg | Model::Mgb => {
+                self.registers.a = if model == Model::Dmg { 0x01 } else { 0xFF };
+                self.registers.f = FlagRegister {
+                    zero: true,
+                    sub: false,
+                    half_carry: checksum_nonzero,
+                    carry: checksum_nonzero,
+                };
+                self.registers.set_bc(0x0013);
+                self.registers.set_de(0x00D8);
+                self.registers.set_hl(0x014D);
+            }
+            Model::Sgb | Model::Sgb2 => {
+                self.registers.a = if model == Model::Sgb { 0x01 } else { 0xFF };
+                self.registers.f = FlagRegister::from(0x00);
+                self.registers.set_bc(0x0014);
+                self.registers.set_de(0x0000);
+                self.registers.set_hl(0xC060);
+            }
+            Model::Cgb | Model::Agb => {
+                // Games check for A = 0x11 to detect CGB hardware
+                self.registers.a = 0x11;
+                self.registers.f = FlagRegister::from(0x80);
+
+                if self.peek(CGB_FLAG_ADDR) & 0x80 != 0 {
+                    self.registers.set_bc(0x0000);
+                    self.registers.set_de(0xFF56);
+                    self.registers.set_hl(0x000D);
+                } else {
+                    let title_hash = self.licensed_title_hash();
+
+                    self.registers.set_bc((title_hash as u16) << 8);
+                    self.registers.set_de(0x0008);
+                    self.registers.set_hl(match title_hash {
+                        0x43 | 0x58 => 0x991A,
+                        _ => 0x007C,
+                    });
+                }
+
+                // The AGB boot ROM ends with an extra INC B, which is how games detect it
+                if model == Model::Agb {
+                    self.inc_b();
+                }
+            }
+        }
+    }
+
+    // Sum of the title bytes, which the CGB boot ROM only computes for Nintendo published games
+    fn licensed_title_hash(&self) -> u8 {
+        let old_licensee = self.peek(OLD_LICENSEE_ADDR);
+        let new_licensee = [
+            self.peek(NEW_LICENSEE_ADDR),
+            self.peek(NEW_LICENSEE_ADDR + 1),
+        ];
+
+        if old_licensee != 0x01 && (old_licensee != 0x33 || new_licensee != *b"01") {
+            return 0;
+        }
+
+        (TITLE_ADDR..TITLE_ADDR + 16).fold(0, |hash, addr| hash.wrapping_add(self.peek(addr)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cpu::test_helpers::make_cpu;
+
+    #[test]
+    fn test_dmg_flags_follow_header_checksum() {
+        let mut cpu = make_cpu();
+
+        cpu.skip_boot_rom(Model::Dmg);
+        assert_eq!(u8::from(cpu.registers.f), 0x80);
+
+        cpu.bus.write(HEADER_CHECKSUM_ADDR, 0x3C);
+        cpu.skip_boot_rom(Model::Dmg);
+
+        assert_eq!(cpu.registers.a, 0x01);
+        assert_eq!(u8::from(cpu.registers.f), 0xB0);
+        assert_eq!(cpu.registers.bc(), 0x0013);
+        assert_eq!(cpu.registers.de(), 0x00D8);
+        assert_eq!(cpu.registers.hl(), 0x014D);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+        assert_eq!(cpu.registers.pc, 0x0100);
+    }
+
+    #[test]
+    fn test_cgb_mode_registers() {
+        let mut cpu = make_cpu();
+        cpu.bus.write(CGB_FLAG_ADDR, 0x80);
+
+        cpu.skip_boot_rom(Model::Cgb);
+
+        assert_eq!(cpu.registers.a, 0x11);
+        assert_eq!(u8::from(cpu.registers.f), 0x80);
+        assert_eq!(cpu.registers.bc(), 0x0000);
+        assert_eq!(cpu.registers.de(), 0xFF56);
+        assert_eq!(cpu.registers.hl(), 0x000D);
+
+        cpu.skip_boot_rom(Model::Agb);
+
+        assert_eq!(u8::from(cpu.registers.f), 0x00);
+        assert_eq!(cpu.registers.bc(), 0x0100);
+    }
+
+    #[test]
+    fn test_cgb_dmg_mode_hashes_nintendo_titles() {
+        let mut cpu = make_cpu();
+        cpu.bus.write(OLD_LICENSEE_ADDR, 0x01);
+        cpu.bus.write(TITLE_ADDR, 0x40);
+        cpu.bus.write(TITLE_ADDR + 15, 0x03);
+
+        cpu.skip_boot_rom(Model::Cgb);
+
+        assert_eq!(cpu.registers.bc(), 0x4300);
+        assert_eq!(cpu.registers.de(), 0x0008);
+        assert_eq!(cpu.registers.hl(), 0x991A);
+
+        // Other publishers get no hash, and so no special palette
+        cpu.bus.write(OLD_LICENSEE_ADDR, 0x33);
+        cpu.skip_boot_rom(Model::Cgb);
+
+        assert_eq!(cpu.registers.bc(), 0x0000);
+        assert_eq!(cpu.registers.hl(), 0x007C);
+    }
+}
@@ -1,5 +1,6 @@
 use super::CPU;
 use super::utils::*;
+use crate::bus::Bus;
 use paste::paste;
 
 macro_rules! register_cmp {
@@ -18,7 +19,7 @@ macro_rules! register_cmp {
     };
 }
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     // Jump instructions
     fn jump(&mut self, offset: i8) {
         self.registers.pc = ((self.registers.pc as i16) + (offset as i16)) as u16;
@@ -93,24 +94,28 @@ impl CPU {
         self.jump_signed_carry_flag(false)
     }
 
-    pub(super) fn jump_16_bit(&mut self) {
+    pub(super) fn read_imm_16_bit(&mut self) -> u16 {
         let low_byte = self.read_from_pc();
         let high_byte = self.read_from_pc();
 
-        self.registers.pc = build_16_bit(high_byte, low_byte);
+        build_16_bit(high_byte, low_byte)
+    }
+
+    pub(super) fn jump_16_bit(&mut self) {
+        self.registers.pc = self.read_imm_16_bit();
     }
 
+    // The target address is always read, even when the jump isn't taken
     fn _jump(&mut self, condition: bool) -> u32 {
-        let mut num_cycles = 3;
-        if condition {
-            self.registers.pc += 2;
-            num_cycles
-        } else {
-            self.jump_16_bit();
+        let addr = self.read_imm_16_bit();
 
-            num_cycles = 4;
-            num_cycles
+        if !condition {
+            return 3;
         }
+
+        self.jump_to_address(addr);
+
+        4
     }
 
     pub(super) fn jump_nz(&mut self) -> u32 {
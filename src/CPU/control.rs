@@ -1,13 +1,211 @@
-use super::CPU;
+use std::{error::Error, fmt};
 
-impl CPU {
+use super::utils::*;
+use super::{CPU, RunState};
+use crate::bus::{Bus, P1_ADDR};
+use crate::timer::DIV_ADDR;
+
+// An illegal opcode hangs the CPU until the system is reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IllegalOpcode {
+    pub opcode: u8,
+    pub pc: u16, // Address the opcode was fetched from
+}
+
+impl fmt::Display for IllegalOpcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CPU locked up on illegal opcode ${:02X} at ${:04X}",
+            self.opcode, self.pc
+        )
+    }
+}
+
+impl Error for IllegalOpcode {}
+
+impl<B: Bus> CPU<B> {
     pub(super) fn nop(&mut self) {}
 
-    // TODO: Research this more and implement
+    /*
+        STOP is 2 bytes long and resets DIV.
+        On CGB with a speed switch armed through KEY1 it switches speed instead of stopping,
+        otherwise the system clock stops until a button is pressed.
+    */
     pub(super) fn stop(&mut self) {
-        self.registers.pc += 1; // Instruction is 2 bytes long
+        wrapping_add(&mut self.registers.pc, 1);
+
+        self.poke(DIV_ADDR, 0);
+
+        if self.bus.speed_switch() {
+            return;
+        }
+
+        self.run_state = RunState::Stopped;
+    }
+
+    /*
+        HALT suspends instruction fetches until an interrupt is pending.
+        If IME is off and an interrupt is already pending, the CPU doesn't halt at all;
+        instead it fails to increment PC on the next fetch (the HALT bug).
+    */
+    pub(super) fn halt(&mut self) {
+        if !self.ime && self.pending_interrupts() != 0 {
+            self.halt_bug = true;
+        } else {
+            self.run_state = RunState::Halted;
+        }
+    }
+
+    pub(super) fn lock_up(&mut self) {
+        // The opcode has already been fetched, so PC is one past it
+        let pc = self.registers.pc.wrapping_sub(1);
+
+        self.run_state = RunState::Locked(IllegalOpcode {
+            opcode: self.peek(pc),
+            pc,
+        });
+    }
+
+    // STOP ends when a button selected through P1 pulls its input line low, whatever IF and IE say
+    pub(super) fn joypad_pressed(&self) -> bool {
+        self.peek(P1_ADDR) & 0x0F != 0x0F
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IllegalOpcode;
+    use crate::bus::SystemBus;
+    use crate::bus::{Bus, IE_ADDR, IF_ADDR, Interrupt, P1_ADDR};
+    use crate::cpu::test_helpers::make_cpu;
+    use crate::cpu::{CPU, RunState};
+    use crate::joypad::Button;
+    use crate::model::Model;
+    use crate::timer::DIV_ADDR;
+
+    #[test]
+    fn test_halt_waits_for_interrupt() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.write(0xC000, 0x76); // HALT
+        cpu.write(IE_ADDR, Interrupt::Timer.mask());
+
+        cpu.tick();
+        cpu.tick();
+        cpu.tick();
+
+        assert_eq!(cpu.run_state(), RunState::Halted);
+        assert_eq!(cpu.registers.pc, 0xC001);
+
+        cpu.bus.request_interrupt(Interrupt::Timer);
+        cpu.tick();
+
+        // IME is off, so execution resumes after the HALT without dispatching
+        assert_eq!(cpu.run_state(), RunState::Running);
+        assert_eq!(cpu.registers.pc, 0xC002);
+    }
+
+    #[test]
+    fn test_halt_bug() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.write(0xC000, 0x76); // HALT
+        cpu.write(0xC001, 0x3C); // INC A
+        cpu.write(IE_ADDR, Interrupt::VBlank.mask());
+        cpu.bus.request_interrupt(Interrupt::VBlank);
+
+        cpu.tick();
+        assert_eq!(cpu.run_state(), RunState::Running);
+
+        cpu.tick();
+        cpu.tick();
+
+        // INC A is executed twice because PC didn't advance past it the first time
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.pc, 0xC002);
+    }
+
+    #[test]
+    fn test_stop_waits_for_joypad() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.write(0xC000, 0x10); // STOP
+        cpu.write(DIV_ADDR, 0xAB);
+        cpu.write(P1_ADDR, 0xEF); // D-pad selected, nothing held
+
+        cpu.tick();
+        cpu.tick();
+
+        assert_eq!(cpu.run_state(), RunState::Stopped);
+        assert_eq!(cpu.read(DIV_ADDR), 0);
+        assert_eq!(cpu.registers.pc, 0xC002);
+
+        // A stale joypad interrupt flag doesn't count as a press
+        cpu.bus.request_interrupt(Interrupt::Joypad);
+        cpu.tick();
+        assert_eq!(cpu.run_state(), RunState::Stopped);
+        assert_eq!(cpu.read(IF_ADDR), Interrupt::Joypad.mask());
+
+        cpu.write(P1_ADDR, 0xEB); // Up
+        cpu.tick();
+
+        assert_eq!(cpu.run_state(), RunState::Running);
+        assert_eq!(cpu.registers.pc, 0xC003);
+    }
+
+    #[test]
+    fn test_stop_resumes_on_button_press() {
+        let mut cpu = CPU::new(SystemBus::from_rom(vec![0; 0x8000], Model::Dmg));
+        cpu.registers.pc = 0xC000;
+        cpu.write(0xC000, 0x10); // STOP
+        cpu.write(0xC002, 0x3C); // INC A
+        cpu.write(P1_ADDR, 0x10); // Select the buttons (A, B, Select, Start)
+        cpu.registers.a = 0;
+
+        for _ in 0..10 {
+            cpu.tick();
+        }
+        assert_eq!(cpu.run_state(), RunState::Stopped);
+
+        // The d-pad isn't selected, so holding it doesn't wake the CPU
+        cpu.bus_mut().press_button(Button::Left);
+        cpu.tick();
+        assert_eq!(cpu.run_state(), RunState::Stopped);
+
+        cpu.bus_mut().press_button(Button::Start);
+        cpu.tick();
+        assert_eq!(cpu.run_state(), RunState::Running);
+        assert_eq!(cpu.registers.a, 1);
     }
 
-    // TODO: Research this more and implement
-    pub(super) fn halt(&mut self) {}
+    #[test]
+    fn test_illegal_opcode_locks_up() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.write(0xC000, 0xD3); // Illegal
+        cpu.write(0xC001, 0x3C); // INC A
+
+        cpu.tick();
+
+        let expected = IllegalOpcode {
+            opcode: 0xD3,
+            pc: 0xC000,
+        };
+        assert_eq!(cpu.run_state(), RunState::Locked(expected));
+
+        cpu.ime = true;
+        cpu.write(IE_ADDR, Interrupt::VBlank.mask());
+        cpu.bus.request_interrupt(Interrupt::VBlank);
+
+        let cycles = cpu.cycles;
+        cpu.tick();
+        cpu.tick();
+
+        // Neither instructions nor interrupts are handled, but time still passes
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.pc, 0xC001);
+        assert_eq!(cpu.cycles, cycles + 2);
+        assert_eq!(cpu.run_state(), RunState::Locked(expected));
+    }
 }
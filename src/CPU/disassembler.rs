@@ -0,0 +1,202 @@
+use std::fmt;
+
+use super::instructions::{Condition, INSTRUCTIONS, Instruction, Operand, PREFIXED_INSTRUCTIONS};
+use super::utils::build_16_bit;
+
+pub struct DisassembledInstruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    // None when the bytes aren't a complete, legal instruction and were emitted as data
+    pub instruction: Option<&'static Instruction>,
+    pub text: String,
+}
+
+impl fmt::Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+// Disassembles a run of code loaded at `addr` into RGBDS syntax, e.g. "ld [hl+], a"
+pub fn disassemble(bytes: &[u8], addr: u16) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+
+    while let Some(instruction) =
+        disassemble_instruction(&bytes[offset..], addr.wrapping_add(offset as u16))
+    {
+        offset += instruction.bytes.len();
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+// Disassembles the single instruction at the start of `bytes`, None if there are no bytes left
+pub fn disassemble_instruction(bytes: &[u8], addr: u16) -> Option<DisassembledInstruction> {
+    let instruction = match bytes {
+        [0xCB, opcode, ..] => &PREFIXED_INSTRUCTIONS[*opcode as usize],
+        [opcode, ..] => &INSTRUCTIONS[*opcode as usize],
+        [] => return None,
+    };
+
+    let length = instruction.length as usize;
+
+    // Illegal opcodes and instructions cut short by the end of the input are emitted as data
+    if instruction.is_illegal() || bytes.len() < length || bytes[0] == 0xCB && bytes.len() < 2 {
+        return Some(DisassembledInstruction {
+            address: addr,
+            bytes: vec![bytes[0]],
+            instruction: None,
+            text: format!("db ${:02X}", bytes[0]),
+        });
+    }
+
+    let bytes = &bytes[..length];
+
+    let operands: Vec<String> = instruction
+        .operands
+        .iter()
+        .map(|operand| format_operand(*operand, bytes, addr))
+        .collect();
+
+    let text = if operands.is_empty() {
+        instruction.mnemonic.to_string()
+    } else {
+        format!("{} {}", instruction.mnemonic, operands.join(", "))
+    };
+
+    Some(DisassembledInstruction {
+        address: addr,
+        bytes: bytes.to_vec(),
+        instruction: Some(instruction),
+        text,
+    })
+}
+
+fn format_signed(value: i8) -> String {
+    if value < 0 {
+        format!("-${:02X}", value.unsigned_abs())
+    } else {
+        format!("${:02X}", value)
+    }
+}
+
+// Immediate operands always directly follow the opcode
+fn format_operand(operand: Operand, bytes: &[u8], addr: u16) -> String {
+    let imm8 = || bytes[1];
+    let imm16 = || build_16_bit(bytes[2], bytes[1]);
+
+    match operand {
+        Operand::A => "a".to_string(),
+        Operand::B => "b".to_string(),
+        Operand::C => "c".to_string(),
+        Operand::D => "d".to_string(),
+        Operand::E => "e".to_string(),
+        Operand::H => "h".to_string(),
+        Operand::L => "l".to_string(),
+        Operand::AF => "af".to_string(),
+        Operand::BC => "bc".to_string(),
+        Operand::DE => "de".to_string(),
+        Operand::HL => "hl".to_string(),
+        Operand::SP => "sp".to_string(),
+        Operand::IndBC => "[bc]".to_string(),
+        Operand::IndDE => "[de]".to_string(),
+        Operand::IndHL => "[hl]".to_string(),
+        Operand::IndHLInc => "[hl+]".to_string(),
+        Operand::IndHLDec => "[hl-]".to_string(),
+        Operand::IndHighC => "[c]".to_string(),
+        Operand::Imm8 => format!("${:02X}", imm8()),
+        Operand::Imm16 => format!("${:04X}", imm16()),
+        Operand::SignedImm8 => format_signed(imm8() as i8),
+        Operand::IndImm16 => format!("[${:04X}]", imm16()),
+        Operand::IndHighImm8 => format!("[$FF{:02X}]", imm8()),
+        // Relative jumps are shown with their absolute target, as RGBDS expects
+        Operand::Offset8 => {
+            let next = addr.wrapping_add(bytes.len() as u16);
+            format!("${:04X}", next.wrapping_add_signed(imm8() as i8 as i16))
+        }
+        Operand::SPOffset8 => {
+            let offset = imm8() as i8;
+            let sign = if offset < 0 { '-' } else { '+' };
+            format!("sp {} ${:02X}", sign, offset.unsigned_abs())
+        }
+        Operand::Cond(Condition::NotZero) => "nz".to_string(),
+        Operand::Cond(Condition::Zero) => "z".to_string(),
+        Operand::Cond(Condition::NotCarry) => "nc".to_string(),
+        Operand::Cond(Condition::Carry) => "c".to_string(),
+        Operand::Vector(vector) => format!("${:02X}", vector),
+        Operand::Bit(bit) => bit.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(bytes: &[u8], addr: u16) -> Vec<String> {
+        disassemble(bytes, addr)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_disassemble_loads() {
+        assert_eq!(
+            texts(
+                &[0x22, 0x3A, 0x01, 0x34, 0x12, 0xEA, 0x00, 0xC0, 0xE0, 0x44],
+                0x0100
+            ),
+            vec![
+                "ld [hl+], a",
+                "ld a, [hl-]",
+                "ld bc, $1234",
+                "ld [$C000], a",
+                "ldh [$FF44], a"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_branches() {
+        assert_eq!(
+            texts(&[0x20, 0xFE, 0xC3, 0x50, 0x01, 0xD8, 0xFF], 0x0150),
+            vec!["jr nz, $0150", "jp $0150", "ret c", "rst $38"]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_prefixed() {
+        assert_eq!(
+            texts(&[0xCB, 0x7C, 0xCB, 0x36, 0xCB, 0xC7], 0),
+            vec!["bit 7, h", "swap [hl]", "set 0, a"]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_signed_operands() {
+        assert_eq!(
+            texts(&[0xF8, 0xFD, 0xE8, 0x05], 0),
+            vec!["ld hl, sp - $03", "add sp, $05"]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_data() {
+        let instructions = disassemble(&[0xD3, 0x00, 0xCD, 0x00], 0);
+
+        assert_eq!(instructions.len(), 4);
+        assert_eq!(instructions[0].to_string(), "db $D3");
+        assert_eq!(instructions[1].to_string(), "nop");
+        assert_eq!(instructions[2].to_string(), "db $CD");
+        assert!(instructions[2].instruction.is_none());
+        assert_eq!(instructions[1].instruction.unwrap().length, 1);
+    }
+
+    #[test]
+    fn test_disassemble_empty_input() {
+        assert!(disassemble_instruction(&[], 0).is_none());
+        assert!(disassemble(&[], 0).is_empty());
+    }
+}
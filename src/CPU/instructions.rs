@@ -1,331 +1,780 @@
 use super::CPU;
+use crate::bus::Bus;
 
 type CPUCycles = u32; // Specifically M-cycle representation, not T-cycle
 
-type InstructionFn = fn(&mut CPU) -> CPUCycles;
+pub(super) type InstructionFn<B> = fn(&mut CPU<B>) -> CPUCycles;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    NotZero,
+    Zero,
+    NotCarry,
+    Carry,
+}
+
+/*
+    Operand kinds, named after the RGBDS operand syntax they disassemble to.
+    Register operands are read or written directly, Ind* operands go through memory.
+*/
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+    IndBC,       // [bc]
+    IndDE,       // [de]
+    IndHL,       // [hl]
+    IndHLInc,    // [hl+]
+    IndHLDec,    // [hl-]
+    IndHighC,    // [c], i.e. $FF00 + C
+    Imm8,        // n8
+    Imm16,       // n16
+    SignedImm8,  // e8 added to SP
+    IndImm16,    // [n16]
+    IndHighImm8, // [n16] where n16 is $FF00 + n8
+    Offset8,     // e8 relative jump target
+    SPOffset8,   // sp + e8
+    Cond(Condition),
+    Vector(u8), // RST target
+    Bit(u8),    // u3 bit index
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlagEffect {
+    Unaffected,
+    Reset,
+    Set,
+    Affected, // Depends on the result
+}
+
+impl FlagEffect {
+    const fn parse(c: u8) -> Self {
+        match c {
+            b'-' => FlagEffect::Unaffected,
+            b'0' => FlagEffect::Reset,
+            b'1' => FlagEffect::Set,
+            _ => FlagEffect::Affected,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlagEffects {
+    pub zero: FlagEffect,
+    pub sub: FlagEffect,
+    pub half_carry: FlagEffect,
+    pub carry: FlagEffect,
+}
+
+impl FlagEffects {
+    // Parses opcode table notation: one character per flag in Z N H C order, e.g. "Z0H-"
+    const fn parse(flags: &str) -> Self {
+        let flags = flags.as_bytes();
+
+        Self {
+            zero: FlagEffect::parse(flags[0]),
+            sub: FlagEffect::parse(flags[1]),
+            half_carry: FlagEffect::parse(flags[2]),
+            carry: FlagEffect::parse(flags[3]),
+        }
+    }
+}
 
 pub struct Instruction {
     pub name: &'static str,
-    pub function: InstructionFn,
+    pub mnemonic: &'static str, // RGBDS mnemonic, e.g. "ld"
+    pub operands: &'static [Operand],
+    pub length: u8, // In bytes, including the opcode (and 0xCB prefix)
+    pub cycles: CPUCycles,
+    pub cycles_not_taken: CPUCycles, // Only differs from cycles for conditional branches
+    pub flags: FlagEffects,
 }
 
 impl Instruction {
-    pub(super) const fn new(name: &'static str, function: InstructionFn) -> Self {
-        Instruction { name, function }
+    pub(super) const fn new(
+        name: &'static str,
+        mnemonic: &'static str,
+        operands: &'static [Operand],
+        length: u8,
+        cycles: CPUCycles,
+        cycles_not_taken: CPUCycles,
+        flags: &str,
+    ) -> Self {
+        Instruction {
+            name,
+            mnemonic,
+            operands,
+            length,
+            cycles,
+            cycles_not_taken,
+            flags: FlagEffects::parse(flags),
+        }
+    }
+
+    pub fn is_illegal(&self) -> bool {
+        self.name == "UNDEF"
     }
 }
 
-macro_rules! instr {
+/*
+    instr!(name, mnemonic, [operands], length, cycles, flags, function)
+    Cycles are either a static count, or "taken / not taken" for conditional branches.
+*/
+macro_rules! instr_info {
     // Used with opcodes that have static cycle counts
-    ($name:expr, $func:expr, $cycles:literal) => {
-        Instruction::new($name, |cpu| {
-            $func(cpu);
-            $cycles
-        })
+    ($name:expr, $mnemonic:expr, [$($operand:expr),*], $length:literal, $cycles:literal, $flags:literal, $func:expr) => {
+        Instruction::new($name, $mnemonic, &[$($operand),*], $length, $cycles, $cycles, $flags)
     };
-    // Allows instruction to return number of cycles - only used for opcodes with dynamic cycle counts
-    ($name:expr, $func:expr) => {
-        Instruction::new($name, $func)
+    // Only used for opcodes with dynamic cycle counts
+    ($name:expr, $mnemonic:expr, [$($operand:expr),*], $length:literal, $taken:literal / $not_taken:literal, $flags:literal, $func:expr) => {
+        Instruction::new($name, $mnemonic, &[$($operand),*], $length, $taken, $not_taken, $flags)
     };
 }
 
-macro_rules! prefixed_instr {
-    ($name: expr, $func: expr) => {
-        Instruction::new($name, |cpu| {
+macro_rules! instr_fn {
+    ($name:expr, $mnemonic:expr, [$($operand:expr),*], $length:literal, $cycles:literal, $flags:literal, $func:expr) => {
+        |cpu| {
             $func(cpu);
-            2 // All prefixed instructions take two CPU cycles
-        })
+            $cycles
+        }
+    };
+    // Allows instruction to return number of cycles
+    ($name:expr, $mnemonic:expr, [$($operand:expr),*], $length:literal, $taken:literal / $not_taken:literal, $flags:literal, $func:expr) => {
+        $func
     };
 }
 
 /*
-    This can just be a normal const array in the future -
-    I declared it static to avoid the language server yelling at me as I add instructions
+    Splits a list of instr! entries into two tables indexed by opcode: the metadata, which doesn't
+    depend on the bus, and the handlers, which are generic over it so memory accesses can be inlined.
 */
+macro_rules! instruction_table {
+    ($info:ident, $handlers:ident, [$(instr!($($entry:tt)*)),* $(,)?]) => {
+        pub(super) const $info: &[Instruction] = &[$(instr_info!($($entry)*)),*];
+
+        impl<B: Bus> CPU<B> {
+            pub(super) const $handlers: [InstructionFn<B>; 256] = [$(instr_fn!($($entry)*)),*];
+        }
+    };
+}
+
+use Condition::*;
+use Operand::*;
+
 // Index of each instruction corresponds to its relevant opcode
-pub(super) const INSTRUCTIONS: &'static [Instruction] = &[
-    instr!("NOP", CPU::nop, 1),
-    instr!("LD_IMM_BC", CPU::ld_imm_bc, 3),
-    instr!("STR_IND_BC_A", CPU::str_ind_bc_a, 2),
-    instr!("INC_BC", CPU::inc_bc, 2),
-    instr!("INC_B", CPU::inc_b, 1),
-    instr!("DEC_B", CPU::dec_b, 1),
-    instr!("LD_IMM_B", CPU::ld_imm_b, 2),
-    instr!("ROTATE_LEFT_A", CPU::rotate_left_a, 1),
-    instr!("STR_SP_MEM", CPU::str_sp_mem, 5),
-    instr!("ADD_HL_BC", CPU::add_hl_bc, 2),
-    instr!("LD_IND_BC_A", CPU::ld_ind_bc_a, 2),
-    instr!("DEC_BC", CPU::dec_bc, 2),
-    instr!("INC_C", CPU::inc_c, 1),
-    instr!("DEC_C", CPU::dec_c, 1),
-    instr!("LD_IMM_C", CPU::ld_imm_c, 2),
-    instr!("ROTATE_RIGHT_A", CPU::rotate_right_a, 1),
-    instr!("STOP", CPU::stop, 1),
-    instr!("LD_IMM_DE", CPU::ld_imm_de, 3),
-    instr!("LD_IND_DE_A", CPU::ld_ind_de_a, 2),
-    instr!("STR_IND_DE_A", CPU::str_ind_de_a, 2),
-    instr!("INC_DE", CPU::inc_de, 2),
-    instr!("INC_D", CPU::inc_d, 1),
-    instr!("DEC_D", CPU::dec_d, 1),
-    instr!("LD_IMM_D", CPU::ld_imm_d, 2),
-    instr!("ROTATE_LEFT_A_WITH_CARRY", CPU::rotate_left_a_with_carry, 1),
-    instr!("JUMP_SIGNED_DEFAULT", CPU::jump_signed_default, 3),
-    instr!("ADD_HL_DE", CPU::nop, 2), // TODO: Implement
-    instr!("LD_IND_DE_A", CPU::ld_ind_de_a, 2),
-    instr!("DEC_DE", CPU::dec_de, 2),
-    instr!("INC_E", CPU::inc_e, 1),
-    instr!("DEC_E", CPU::dec_e, 1),
-    instr!("LD_IMM_E", CPU::ld_imm_e, 2),
-    instr!(
-        "ROTATE_RIGHT_A_WITH_CARRY",
-        CPU::rotate_right_a_with_carry,
-        1
-    ),
-    instr!("JUMP_SIGNED_ZERO_FLAG_OFF", CPU::jump_signed_zero_flag_off), // Dynamic cycle count
-    instr!("LD_IMM_HL", CPU::ld_imm_hl, 3),
-    instr!("STR_IND_HL_A_ADD", CPU::str_ind_hl_a_add, 2),
-    instr!("INC_HL", CPU::inc_hl, 2),
-    instr!("INC_H", CPU::inc_h, 1),
-    instr!("DEC_H", CPU::dec_h, 1),
-    instr!("LD_IMM_H", CPU::ld_imm_h, 2),
-    instr!("DAA", CPU::binary_coded_decimal, 1),
-    instr!("JUMP_SIGNED_ZERO_FLAG_ON", CPU::jump_signed_zero_flag_on), // Dynamic cycle count
-    instr!("ADD_HL_HL", CPU::nop, 2),                                  // TODO: Implement
-    instr!("LD_IND_HL_A_ADD", CPU::ld_ind_hl_a_add, 2),
-    instr!("DEC_HL", CPU::dec_hl, 2),
-    instr!("INC_L", CPU::inc_l, 1),
-    instr!("DEC_L", CPU::dec_l, 1),
-    instr!("LD_IMM_E", CPU::ld_imm_l, 2),
-    instr!("CPL", CPU::flip_register_a, 1),
-    instr!(
-        "JUMP_SIGNED_CARRY_FLAG_OFF",
-        CPU::jump_signed_carry_flag_off
-    ), // Dynamic cycle count
-    instr!("LD_IMM_SP", CPU::ld_imm_sp, 3),
-    instr!("STR_IND_HL_A_SUB", CPU::str_ind_hl_a_sub, 2),
-    instr!("INC_SP", CPU::inc_sp, 2),
-    instr!("INC_IND_HL", CPU::inc_ind_hl, 3),
-    instr!("DEC_IND_HL", CPU::dec_ind_hl, 3),
-    instr!("STR_IMM_IND_HL", CPU::str_imm_ind_hl, 3),
-    instr!("SET_CARRY_FLAG", CPU::set_carry_flag, 1),
-    instr!("JUMP_SIGNED_CARRY_FLAG_ON", CPU::jump_signed_carry_flag_on),
-    instr!("ADD_HL_SP", CPU::nop, 2), // TODO: Implement
-    instr!("LD_IND_HL_A_SUB", CPU::ld_ind_hl_a_sub, 2),
-    instr!("DEC_SP", CPU::dec_sp, 2),
-    instr!("INC_A", CPU::inc_a, 1),
-    instr!("DEC_A", CPU::dec_a, 1),
-    instr!("LD_IMM_A", CPU::ld_imm_a, 2),
-    instr!("CCF", CPU::flip_carry_flag, 1),
-    instr!("LD_B_B", CPU::nop, 1),
-    instr!("LD_B_C", CPU::ld_b_c, 1),
-    instr!("LD_B_D", CPU::ld_b_d, 1),
-    instr!("LD_B_E", CPU::ld_b_e, 1),
-    instr!("LD_B_H", CPU::ld_b_h, 1),
-    instr!("LD_B_L", CPU::ld_b_l, 1),
-    instr!("LD_IND_HL_B", CPU::ld_ind_hl_b, 2),
-    instr!("LD_B_A", CPU::ld_b_a, 1),
-    instr!("LD_C_B", CPU::ld_c_b, 1),
-    instr!("LD_C_C", CPU::nop, 1),
-    instr!("LD_C_D", CPU::ld_c_d, 1),
-    instr!("LD_C_E", CPU::ld_c_e, 1),
-    instr!("LD_C_H", CPU::ld_c_h, 1),
-    instr!("LD_C_L", CPU::ld_c_l, 1),
-    instr!("LD_IND_HL_C", CPU::ld_ind_hl_c, 2),
-    instr!("LD_C_A", CPU::ld_c_a, 1),
-    instr!("LD_D_B", CPU::ld_d_b, 1),
-    instr!("LD_D_C", CPU::ld_d_c, 1),
-    instr!("LD_D_D", CPU::nop, 1),
-    instr!("LD_D_E", CPU::ld_d_e, 1),
-    instr!("LD_D_H", CPU::ld_d_h, 1),
-    instr!("LD_D_L", CPU::ld_d_l, 1),
-    instr!("LD_IND_HL_D", CPU::ld_ind_hl_d, 2),
-    instr!("LD_D_A", CPU::ld_d_a, 1),
-    instr!("LD_E_B", CPU::ld_e_b, 1),
-    instr!("LD_E_C", CPU::ld_e_c, 1),
-    instr!("LD_E_D", CPU::ld_e_d, 1),
-    instr!("LD_E_E", CPU::nop, 1),
-    instr!("LD_E_H", CPU::ld_e_h, 1),
-    instr!("LD_E_L", CPU::ld_e_l, 1),
-    instr!("LD_IND_HL_E", CPU::ld_ind_hl_e, 2),
-    instr!("LD_E_A", CPU::ld_h_a, 1),
-    instr!("LD_H_B", CPU::ld_h_b, 1),
-    instr!("LD_H_C", CPU::ld_h_c, 1),
-    instr!("LD_H_D", CPU::ld_h_d, 1),
-    instr!("LD_H_E", CPU::ld_h_e, 1),
-    instr!("LD_H_H", CPU::nop, 1),
-    instr!("LD_H_L", CPU::ld_h_l, 1),
-    instr!("LD_IND_HL_H", CPU::ld_ind_hl_h, 2),
-    instr!("LD_H_A", CPU::ld_e_a, 1),
-    instr!("LD_L_B", CPU::ld_l_b, 1),
-    instr!("LD_L_C", CPU::ld_l_c, 1),
-    instr!("LD_L_D", CPU::ld_l_d, 1),
-    instr!("LD_L_E", CPU::ld_l_e, 1),
-    instr!("LD_L_H", CPU::ld_l_h, 1),
-    instr!("LD_L_L", CPU::nop, 1),
-    instr!("LD_IND_HL_L", CPU::ld_ind_hl_l, 2),
-    instr!("LD_L_A", CPU::ld_l_a, 1),
-    instr!("STR_IND_HL_B", CPU::str_ind_hl_b, 2),
-    instr!("STR_IND_HL_C", CPU::str_ind_hl_c, 2),
-    instr!("STR_IND_HL_D", CPU::str_ind_hl_d, 2),
-    instr!("STR_IND_HL_E", CPU::str_ind_hl_e, 2),
-    instr!("STR_IND_HL_H", CPU::str_ind_hl_h, 2),
-    instr!("STR_IND_HL_L", CPU::str_ind_hl_l, 2),
-    instr!("HALT", CPU::halt, 1),
-    instr!("STR_IND_HL_A", CPU::str_ind_hl_a, 2),
-    instr!("LD_A_B", CPU::ld_a_b, 1),
-    instr!("LD_A_C", CPU::ld_a_c, 1),
-    instr!("LD_A_D", CPU::ld_a_d, 1),
-    instr!("LD_A_E", CPU::ld_a_e, 1),
-    instr!("LD_A_H", CPU::ld_a_h, 1),
-    instr!("LD_A_L", CPU::ld_a_l, 1),
-    instr!("LD_IND_HL_A", CPU::ld_ind_hl_a, 2),
-    instr!("LD_A_A", CPU::nop, 1),
-    instr!("ADD_A_B", CPU::add_a_b, 1),
-    instr!("ADD_A_C", CPU::add_a_c, 1),
-    instr!("ADD_A_D", CPU::add_a_d, 1),
-    instr!("ADD_A_E", CPU::add_a_e, 1),
-    instr!("ADD_A_H", CPU::add_a_h, 1),
-    instr!("ADD_A_L", CPU::add_a_l, 1),
-    instr!("ADD_IND_HL_A", CPU::add_ind_hl_a, 2),
-    instr!("ADD_A_A", CPU::add_a_a, 1),
-    instr!("ADD_A_B_WITH_CARRY", CPU::add_a_b_with_carry, 1),
-    instr!("ADD_A_C_WITH_CARRY", CPU::add_a_c_with_carry, 1),
-    instr!("ADD_A_D_WITH_CARRY", CPU::add_a_d_with_carry, 1),
-    instr!("ADD_A_E_WITH_CARRY", CPU::add_a_e_with_carry, 1),
-    instr!("ADD_A_H_WITH_CARRY", CPU::add_a_h_with_carry, 1),
-    instr!("ADD_A_L_WITH_CARRY", CPU::add_a_l_with_carry, 1),
-    instr!("ADD_IND_HL_A_WITH_CARRY", CPU::add_ind_hl_a_with_carry, 2),
-    instr!("ADD_A_A_WITH_CARRY", CPU::add_a_a_with_carry, 1),
-    instr!("SUB_A_B", CPU::subtract_a_b, 1),
-    instr!("SUB_A_C", CPU::subtract_a_c, 1),
-    instr!("SUB_A_D", CPU::subtract_a_d, 1),
-    instr!("SUB_A_E", CPU::subtract_a_e, 1),
-    instr!("SUB_A_H", CPU::subtract_a_h, 1),
-    instr!("SUB_A_L", CPU::subtract_a_l, 1),
-    instr!("SUB_IND_HL_A", CPU::subtract_ind_hl_a, 2),
-    instr!("SUB_A_A", CPU::subtract_a_a, 1),
-    instr!("SUB_A_B_WITH_CARRY", CPU::subtract_a_b_with_carry, 1),
-    instr!("SUB_A_C_WITH_CARRY", CPU::subtract_a_c_with_carry, 1),
-    instr!("SUB_A_D_WITH_CARRY", CPU::subtract_a_d_with_carry, 1),
-    instr!("SUB_A_E_WITH_CARRY", CPU::subtract_a_e_with_carry, 1),
-    instr!("SUB_A_H_WITH_CARRY", CPU::subtract_a_h_with_carry, 1),
-    instr!("SUB_A_L_WITH_CARRY", CPU::subtract_a_l_with_carry, 1),
-    instr!(
-        "SUB_IND_HL_A_WITH_CARRY",
-        CPU::subtract_ind_hl_a_with_carry,
-        2
-    ),
-    instr!("SUB_A_A_WITH_CARRY", CPU::subtract_a_a_with_carry, 1),
-    instr!("AND_A_B", CPU::and_a_b, 1),
-    instr!("AND_A_C", CPU::and_a_c, 1),
-    instr!("AND_A_D", CPU::and_a_d, 1),
-    instr!("AND_A_E", CPU::and_a_e, 1),
-    instr!("AND_A_H", CPU::and_a_h, 1),
-    instr!("AND_A_L", CPU::and_a_l, 1),
-    instr!("AND_IND_HL_A", CPU::and_ind_hl_a, 2),
-    instr!("AND_A_A", CPU::and_a_a, 1),
-    instr!("XOR_A_B", CPU::xor_a_b, 1),
-    instr!("XOR_A_C", CPU::xor_a_c, 1),
-    instr!("XOR_A_D", CPU::xor_a_d, 1),
-    instr!("XOR_A_E", CPU::xor_a_e, 1),
-    instr!("XOR_A_H", CPU::xor_a_h, 1),
-    instr!("XOR_A_L", CPU::xor_a_l, 1),
-    instr!("XOR_IND_HL_A", CPU::xor_ind_hl_a, 2),
-    instr!("XOR_A_A", CPU::xor_a_a, 1), // Should this be a nop?
-    instr!("OR_A_B", CPU::or_a_b, 1),
-    instr!("OR_A_C", CPU::or_a_c, 1),
-    instr!("OR_A_D", CPU::or_a_d, 1),
-    instr!("OR_A_E", CPU::or_a_e, 1),
-    instr!("OR_A_H", CPU::or_a_h, 1),
-    instr!("OR_A_L", CPU::or_a_l, 1),
-    instr!("OR_IND_HL_A", CPU::or_ind_hl_a, 2),
-    instr!("OR_A_A", CPU::or_a_a, 1), // Should this be a nop?
-    instr!("CMP_A_B", CPU::cmp_a_b, 1),
-    instr!("CMP_A_C", CPU::cmp_a_c, 1),
-    instr!("CMP_A_D", CPU::cmp_a_d, 1),
-    instr!("CMP_A_E", CPU::cmp_a_e, 1),
-    instr!("CMP_A_H", CPU::cmp_a_h, 1),
-    instr!("CMP_A_L", CPU::cmp_a_l, 1),
-    instr!("CMP_IND_HL_A", CPU::cmp_ind_hl_a, 2),
-    instr!("CMP_A_A", CPU::cmp_a_a, 1), // not a nop
-    instr!("RET_NZ", CPU::ret_nz),
-    instr!("POP_BC", CPU::pop_bc, 3),
-    instr!("JMP_NZ", CPU::jump_nz),
-    instr!("JMP_IMM", CPU::jump_16_bit, 4),
-    instr!("CALL_NZ", CPU::call_nz),
-    instr!("PUSH_BC", CPU::push_bc, 4),
-    instr!("ADD_IMM_A", CPU::add_imm_a, 2),
-    instr!("RST_0", CPU::reset_00, 4),
-    instr!("RET_Z", CPU::ret_z),
-    instr!("RET", CPU::instr_return, 4),
-    instr!("JMP_Z", CPU::jump_z),
-    instr!("PREFIX", |cpu: &mut CPU| {
+instruction_table! { INSTRUCTIONS, HANDLERS, [
+    instr!("NOP", "nop", [], 1, 1, "----", CPU::nop),
+    instr!("LD_IMM_BC", "ld", [BC, Imm16], 3, 3, "----", CPU::ld_imm_bc),
+    instr!("STR_IND_BC_A", "ld", [IndBC, A], 1, 2, "----", CPU::str_ind_bc_a),
+    instr!("INC_BC", "inc", [BC], 1, 2, "----", CPU::inc_bc),
+    instr!("INC_B", "inc", [B], 1, 1, "Z0H-", CPU::inc_b),
+    instr!("DEC_B", "dec", [B], 1, 1, "Z1H-", CPU::dec_b),
+    instr!("LD_IMM_B", "ld", [B, Imm8], 2, 2, "----", CPU::ld_imm_b),
+    instr!("ROTATE_LEFT_A", "rlca", [], 1, 1, "000C", CPU::rotate_left_a),
+    instr!("STR_SP_MEM", "ld", [IndImm16, SP], 3, 5, "----", CPU::str_sp_mem),
+    instr!("ADD_HL_BC", "add", [HL, BC], 1, 2, "-0HC", CPU::add_hl_bc),
+    instr!("LD_IND_BC_A", "ld", [A, IndBC], 1, 2, "----", CPU::ld_ind_bc_a),
+    instr!("DEC_BC", "dec", [BC], 1, 2, "----", CPU::dec_bc),
+    instr!("INC_C", "inc", [C], 1, 1, "Z0H-", CPU::inc_c),
+    instr!("DEC_C", "dec", [C], 1, 1, "Z1H-", CPU::dec_c),
+    instr!("LD_IMM_C", "ld", [C, Imm8], 2, 2, "----", CPU::ld_imm_c),
+    instr!("ROTATE_RIGHT_A", "rrca", [], 1, 1, "000C", CPU::rotate_right_a),
+    instr!("STOP", "stop", [], 2, 1, "----", CPU::stop),
+    instr!("LD_IMM_DE", "ld", [DE, Imm16], 3, 3, "----", CPU::ld_imm_de),
+    instr!("STR_IND_DE_A", "ld", [IndDE, A], 1, 2, "----", CPU::str_ind_de_a),
+    instr!("INC_DE", "inc", [DE], 1, 2, "----", CPU::inc_de),
+    instr!("INC_D", "inc", [D], 1, 1, "Z0H-", CPU::inc_d),
+    instr!("DEC_D", "dec", [D], 1, 1, "Z1H-", CPU::dec_d),
+    instr!("LD_IMM_D", "ld", [D, Imm8], 2, 2, "----", CPU::ld_imm_d),
+    instr!("ROTATE_LEFT_A_WITH_CARRY", "rla", [], 1, 1, "000C", CPU::rotate_left_a_with_carry),
+    instr!("JUMP_SIGNED_DEFAULT", "jr", [Offset8], 2, 3, "----", CPU::jump_signed_default),
+    instr!("ADD_HL_DE", "add", [HL, DE], 1, 2, "-0HC", CPU::add_hl_de),
+    instr!("LD_IND_DE_A", "ld", [A, IndDE], 1, 2, "----", CPU::ld_ind_de_a),
+    instr!("DEC_DE", "dec", [DE], 1, 2, "----", CPU::dec_de),
+    instr!("INC_E", "inc", [E], 1, 1, "Z0H-", CPU::inc_e),
+    instr!("DEC_E", "dec", [E], 1, 1, "Z1H-", CPU::dec_e),
+    instr!("LD_IMM_E", "ld", [E, Imm8], 2, 2, "----", CPU::ld_imm_e),
+    instr!("ROTATE_RIGHT_A_WITH_CARRY", "rra", [], 1, 1, "000C", CPU::rotate_right_a_with_carry),
+    instr!("JUMP_SIGNED_ZERO_FLAG_OFF", "jr", [Cond(NotZero), Offset8], 2, 3 / 2, "----", CPU::jump_signed_zero_flag_off),
+    instr!("LD_IMM_HL", "ld", [HL, Imm16], 3, 3, "----", CPU::ld_imm_hl),
+    instr!("STR_IND_HL_A_ADD", "ld", [IndHLInc, A], 1, 2, "----", CPU::str_ind_hl_a_add),
+    instr!("INC_HL", "inc", [HL], 1, 2, "----", CPU::inc_hl),
+    instr!("INC_H", "inc", [H], 1, 1, "Z0H-", CPU::inc_h),
+    instr!("DEC_H", "dec", [H], 1, 1, "Z1H-", CPU::dec_h),
+    instr!("LD_IMM_H", "ld", [H, Imm8], 2, 2, "----", CPU::ld_imm_h),
+    instr!("DAA", "daa", [], 1, 1, "Z-0C", CPU::binary_coded_decimal),
+    instr!("JUMP_SIGNED_ZERO_FLAG_ON", "jr", [Cond(Zero), Offset8], 2, 3 / 2, "----", CPU::jump_signed_zero_flag_on),
+    instr!("ADD_HL_HL", "add", [HL, HL], 1, 2, "-0HC", CPU::add_hl_hl),
+    instr!("LD_IND_HL_A_ADD", "ld", [A, IndHLInc], 1, 2, "----", CPU::ld_ind_hl_a_add),
+    instr!("DEC_HL", "dec", [HL], 1, 2, "----", CPU::dec_hl),
+    instr!("INC_L", "inc", [L], 1, 1, "Z0H-", CPU::inc_l),
+    instr!("DEC_L", "dec", [L], 1, 1, "Z1H-", CPU::dec_l),
+    instr!("LD_IMM_L", "ld", [L, Imm8], 2, 2, "----", CPU::ld_imm_l),
+    instr!("CPL", "cpl", [], 1, 1, "-11-", CPU::flip_register_a),
+    instr!("JUMP_SIGNED_CARRY_FLAG_OFF", "jr", [Cond(NotCarry), Offset8], 2, 3 / 2, "----", CPU::jump_signed_carry_flag_off),
+    instr!("LD_IMM_SP", "ld", [SP, Imm16], 3, 3, "----", CPU::ld_imm_sp),
+    instr!("STR_IND_HL_A_SUB", "ld", [IndHLDec, A], 1, 2, "----", CPU::str_ind_hl_a_sub),
+    instr!("INC_SP", "inc", [SP], 1, 2, "----", CPU::inc_sp),
+    instr!("INC_IND_HL", "inc", [IndHL], 1, 3, "Z0H-", CPU::inc_ind_hl),
+    instr!("DEC_IND_HL", "dec", [IndHL], 1, 3, "Z1H-", CPU::dec_ind_hl),
+    instr!("STR_IMM_IND_HL", "ld", [IndHL, Imm8], 2, 3, "----", CPU::str_imm_ind_hl),
+    instr!("SET_CARRY_FLAG", "scf", [], 1, 1, "-001", CPU::set_carry_flag),
+    instr!("JUMP_SIGNED_CARRY_FLAG_ON", "jr", [Cond(Carry), Offset8], 2, 3 / 2, "----", CPU::jump_signed_carry_flag_on),
+    instr!("ADD_HL_SP", "add", [HL, SP], 1, 2, "-0HC", CPU::add_hl_sp),
+    instr!("LD_IND_HL_A_SUB", "ld", [A, IndHLDec], 1, 2, "----", CPU::ld_ind_hl_a_sub),
+    instr!("DEC_SP", "dec", [SP], 1, 2, "----", CPU::dec_sp),
+    instr!("INC_A", "inc", [A], 1, 1, "Z0H-", CPU::inc_a),
+    instr!("DEC_A", "dec", [A], 1, 1, "Z1H-", CPU::dec_a),
+    instr!("LD_IMM_A", "ld", [A, Imm8], 2, 2, "----", CPU::ld_imm_a),
+    instr!("CCF", "ccf", [], 1, 1, "-00C", CPU::flip_carry_flag),
+    instr!("LD_B_B", "ld", [B, B], 1, 1, "----", CPU::nop),
+    instr!("LD_B_C", "ld", [B, C], 1, 1, "----", CPU::ld_b_c),
+    instr!("LD_B_D", "ld", [B, D], 1, 1, "----", CPU::ld_b_d),
+    instr!("LD_B_E", "ld", [B, E], 1, 1, "----", CPU::ld_b_e),
+    instr!("LD_B_H", "ld", [B, H], 1, 1, "----", CPU::ld_b_h),
+    instr!("LD_B_L", "ld", [B, L], 1, 1, "----", CPU::ld_b_l),
+    instr!("LD_IND_HL_B", "ld", [B, IndHL], 1, 2, "----", CPU::ld_ind_hl_b),
+    instr!("LD_B_A", "ld", [B, A], 1, 1, "----", CPU::ld_b_a),
+    instr!("LD_C_B", "ld", [C, B], 1, 1, "----", CPU::ld_c_b),
+    instr!("LD_C_C", "ld", [C, C], 1, 1, "----", CPU::nop),
+    instr!("LD_C_D", "ld", [C, D], 1, 1, "----", CPU::ld_c_d),
+    instr!("LD_C_E", "ld", [C, E], 1, 1, "----", CPU::ld_c_e),
+    instr!("LD_C_H", "ld", [C, H], 1, 1, "----", CPU::ld_c_h),
+    instr!("LD_C_L", "ld", [C, L], 1, 1, "----", CPU::ld_c_l),
+    instr!("LD_IND_HL_C", "ld", [C, IndHL], 1, 2, "----", CPU::ld_ind_hl_c),
+    instr!("LD_C_A", "ld", [C, A], 1, 1, "----", CPU::ld_c_a),
+    instr!("LD_D_B", "ld", [D, B], 1, 1, "----", CPU::ld_d_b),
+    instr!("LD_D_C", "ld", [D, C], 1, 1, "----", CPU::ld_d_c),
+    instr!("LD_D_D", "ld", [D, D], 1, 1, "----", CPU::nop),
+    instr!("LD_D_E", "ld", [D, E], 1, 1, "----", CPU::ld_d_e),
+    instr!("LD_D_H", "ld", [D, H], 1, 1, "----", CPU::ld_d_h),
+    instr!("LD_D_L", "ld", [D, L], 1, 1, "----", CPU::ld_d_l),
+    instr!("LD_IND_HL_D", "ld", [D, IndHL], 1, 2, "----", CPU::ld_ind_hl_d),
+    instr!("LD_D_A", "ld", [D, A], 1, 1, "----", CPU::ld_d_a),
+    instr!("LD_E_B", "ld", [E, B], 1, 1, "----", CPU::ld_e_b),
+    instr!("LD_E_C", "ld", [E, C], 1, 1, "----", CPU::ld_e_c),
+    instr!("LD_E_D", "ld", [E, D], 1, 1, "----", CPU::ld_e_d),
+    instr!("LD_E_E", "ld", [E, E], 1, 1, "----", CPU::nop),
+    instr!("LD_E_H", "ld", [E, H], 1, 1, "----", CPU::ld_e_h),
+    instr!("LD_E_L", "ld", [E, L], 1, 1, "----", CPU::ld_e_l),
+    instr!("LD_IND_HL_E", "ld", [E, IndHL], 1, 2, "----", CPU::ld_ind_hl_e),
+    instr!("LD_E_A", "ld", [E, A], 1, 1, "----", CPU::ld_e_a),
+    instr!("LD_H_B", "ld", [H, B], 1, 1, "----", CPU::ld_h_b),
+    instr!("LD_H_C", "ld", [H, C], 1, 1, "----", CPU::ld_h_c),
+    instr!("LD_H_D", "ld", [H, D], 1, 1, "----", CPU::ld_h_d),
+    instr!("LD_H_E", "ld", [H, E], 1, 1, "----", CPU::ld_h_e),
+    instr!("LD_H_H", "ld", [H, H], 1, 1, "----", CPU::nop),
+    instr!("LD_H_L", "ld", [H, L], 1, 1, "----", CPU::ld_h_l),
+    instr!("LD_IND_HL_H", "ld", [H, IndHL], 1, 2, "----", CPU::ld_ind_hl_h),
+    instr!("LD_H_A", "ld", [H, A], 1, 1, "----", CPU::ld_h_a),
+    instr!("LD_L_B", "ld", [L, B], 1, 1, "----", CPU::ld_l_b),
+    instr!("LD_L_C", "ld", [L, C], 1, 1, "----", CPU::ld_l_c),
+    instr!("LD_L_D", "ld", [L, D], 1, 1, "----", CPU::ld_l_d),
+    instr!("LD_L_E", "ld", [L, E], 1, 1, "----", CPU::ld_l_e),
+    instr!("LD_L_H", "ld", [L, H], 1, 1, "----", CPU::ld_l_h),
+    instr!("LD_L_L", "ld", [L, L], 1, 1, "----", CPU::nop),
+    instr!("LD_IND_HL_L", "ld", [L, IndHL], 1, 2, "----", CPU::ld_ind_hl_l),
+    instr!("LD_L_A", "ld", [L, A], 1, 1, "----", CPU::ld_l_a),
+    instr!("STR_IND_HL_B", "ld", [IndHL, B], 1, 2, "----", CPU::str_ind_hl_b),
+    instr!("STR_IND_HL_C", "ld", [IndHL, C], 1, 2, "----", CPU::str_ind_hl_c),
+    instr!("STR_IND_HL_D", "ld", [IndHL, D], 1, 2, "----", CPU::str_ind_hl_d),
+    instr!("STR_IND_HL_E", "ld", [IndHL, E], 1, 2, "----", CPU::str_ind_hl_e),
+    instr!("STR_IND_HL_H", "ld", [IndHL, H], 1, 2, "----", CPU::str_ind_hl_h),
+    instr!("STR_IND_HL_L", "ld", [IndHL, L], 1, 2, "----", CPU::str_ind_hl_l),
+    instr!("HALT", "halt", [], 1, 1, "----", CPU::halt),
+    instr!("STR_IND_HL_A", "ld", [IndHL, A], 1, 2, "----", CPU::str_ind_hl_a),
+    instr!("LD_A_B", "ld", [A, B], 1, 1, "----", CPU::ld_a_b),
+    instr!("LD_A_C", "ld", [A, C], 1, 1, "----", CPU::ld_a_c),
+    instr!("LD_A_D", "ld", [A, D], 1, 1, "----", CPU::ld_a_d),
+    instr!("LD_A_E", "ld", [A, E], 1, 1, "----", CPU::ld_a_e),
+    instr!("LD_A_H", "ld", [A, H], 1, 1, "----", CPU::ld_a_h),
+    instr!("LD_A_L", "ld", [A, L], 1, 1, "----", CPU::ld_a_l),
+    instr!("LD_IND_HL_A", "ld", [A, IndHL], 1, 2, "----", CPU::ld_ind_hl_a),
+    instr!("LD_A_A", "ld", [A, A], 1, 1, "----", CPU::nop),
+    instr!("ADD_A_B", "add", [A, B], 1, 1, "Z0HC", CPU::add_a_b),
+    instr!("ADD_A_C", "add", [A, C], 1, 1, "Z0HC", CPU::add_a_c),
+    instr!("ADD_A_D", "add", [A, D], 1, 1, "Z0HC", CPU::add_a_d),
+    instr!("ADD_A_E", "add", [A, E], 1, 1, "Z0HC", CPU::add_a_e),
+    instr!("ADD_A_H", "add", [A, H], 1, 1, "Z0HC", CPU::add_a_h),
+    instr!("ADD_A_L", "add", [A, L], 1, 1, "Z0HC", CPU::add_a_l),
+    instr!("ADD_IND_HL_A", "add", [A, IndHL], 1, 2, "Z0HC", CPU::add_ind_hl_a),
+    instr!("ADD_A_A", "add", [A, A], 1, 1, "Z0HC", CPU::add_a_a),
+    instr!("ADD_A_B_WITH_CARRY", "adc", [A, B], 1, 1, "Z0HC", CPU::add_a_b_with_carry),
+    instr!("ADD_A_C_WITH_CARRY", "adc", [A, C], 1, 1, "Z0HC", CPU::add_a_c_with_carry),
+    instr!("ADD_A_D_WITH_CARRY", "adc", [A, D], 1, 1, "Z0HC", CPU::add_a_d_with_carry),
+    instr!("ADD_A_E_WITH_CARRY", "adc", [A, E], 1, 1, "Z0HC", CPU::add_a_e_with_carry),
+    instr!("ADD_A_H_WITH_CARRY", "adc", [A, H], 1, 1, "Z0HC", CPU::add_a_h_with_carry),
+    instr!("ADD_A_L_WITH_CARRY", "adc", [A, L], 1, 1, "Z0HC", CPU::add_a_l_with_carry),
+    instr!("ADD_IND_HL_A_WITH_CARRY", "adc", [A, IndHL], 1, 2, "Z0HC", CPU::add_ind_hl_a_with_carry),
+    instr!("ADD_A_A_WITH_CARRY", "adc", [A, A], 1, 1, "Z0HC", CPU::add_a_a_with_carry),
+    instr!("SUB_A_B", "sub", [A, B], 1, 1, "Z1HC", CPU::subtract_a_b),
+    instr!("SUB_A_C", "sub", [A, C], 1, 1, "Z1HC", CPU::subtract_a_c),
+    instr!("SUB_A_D", "sub", [A, D], 1, 1, "Z1HC", CPU::subtract_a_d),
+    instr!("SUB_A_E", "sub", [A, E], 1, 1, "Z1HC", CPU::subtract_a_e),
+    instr!("SUB_A_H", "sub", [A, H], 1, 1, "Z1HC", CPU::subtract_a_h),
+    instr!("SUB_A_L", "sub", [A, L], 1, 1, "Z1HC", CPU::subtract_a_l),
+    instr!("SUB_IND_HL_A", "sub", [A, IndHL], 1, 2, "Z1HC", CPU::subtract_ind_hl_a),
+    instr!("SUB_A_A", "sub", [A, A], 1, 1, "Z1HC", CPU::subtract_a_a),
+    instr!("SUB_A_B_WITH_CARRY", "sbc", [A, B], 1, 1, "Z1HC", CPU::subtract_a_b_with_carry),
+    instr!("SUB_A_C_WITH_CARRY", "sbc", [A, C], 1, 1, "Z1HC", CPU::subtract_a_c_with_carry),
+    instr!("SUB_A_D_WITH_CARRY", "sbc", [A, D], 1, 1, "Z1HC", CPU::subtract_a_d_with_carry),
+    instr!("SUB_A_E_WITH_CARRY", "sbc", [A, E], 1, 1, "Z1HC", CPU::subtract_a_e_with_carry),
+    instr!("SUB_A_H_WITH_CARRY", "sbc", [A, H], 1, 1, "Z1HC", CPU::subtract_a_h_with_carry),
+    instr!("SUB_A_L_WITH_CARRY", "sbc", [A, L], 1, 1, "Z1HC", CPU::subtract_a_l_with_carry),
+    instr!("SUB_IND_HL_A_WITH_CARRY", "sbc", [A, IndHL], 1, 2, "Z1HC", CPU::subtract_ind_hl_a_with_carry),
+    instr!("SUB_A_A_WITH_CARRY", "sbc", [A, A], 1, 1, "Z1HC", CPU::subtract_a_a_with_carry),
+    instr!("AND_A_B", "and", [A, B], 1, 1, "Z010", CPU::and_a_b),
+    instr!("AND_A_C", "and", [A, C], 1, 1, "Z010", CPU::and_a_c),
+    instr!("AND_A_D", "and", [A, D], 1, 1, "Z010", CPU::and_a_d),
+    instr!("AND_A_E", "and", [A, E], 1, 1, "Z010", CPU::and_a_e),
+    instr!("AND_A_H", "and", [A, H], 1, 1, "Z010", CPU::and_a_h),
+    instr!("AND_A_L", "and", [A, L], 1, 1, "Z010", CPU::and_a_l),
+    instr!("AND_IND_HL_A", "and", [A, IndHL], 1, 2, "Z010", CPU::and_ind_hl_a),
+    instr!("AND_A_A", "and", [A, A], 1, 1, "Z010", CPU::and_a_a),
+    instr!("XOR_A_B", "xor", [A, B], 1, 1, "Z000", CPU::xor_a_b),
+    instr!("XOR_A_C", "xor", [A, C], 1, 1, "Z000", CPU::xor_a_c),
+    instr!("XOR_A_D", "xor", [A, D], 1, 1, "Z000", CPU::xor_a_d),
+    instr!("XOR_A_E", "xor", [A, E], 1, 1, "Z000", CPU::xor_a_e),
+    instr!("XOR_A_H", "xor", [A, H], 1, 1, "Z000", CPU::xor_a_h),
+    instr!("XOR_A_L", "xor", [A, L], 1, 1, "Z000", CPU::xor_a_l),
+    instr!("XOR_IND_HL_A", "xor", [A, IndHL], 1, 2, "Z000", CPU::xor_ind_hl_a),
+    instr!("XOR_A_A", "xor", [A, A], 1, 1, "Z000", CPU::xor_a_a),
+    instr!("OR_A_B", "or", [A, B], 1, 1, "Z000", CPU::or_a_b),
+    instr!("OR_A_C", "or", [A, C], 1, 1, "Z000", CPU::or_a_c),
+    instr!("OR_A_D", "or", [A, D], 1, 1, "Z000", CPU::or_a_d),
+    instr!("OR_A_E", "or", [A, E], 1, 1, "Z000", CPU::or_a_e),
+    instr!("OR_A_H", "or", [A, H], 1, 1, "Z000", CPU::or_a_h),
+    instr!("OR_A_L", "or", [A, L], 1, 1, "Z000", CPU::or_a_l),
+    instr!("OR_IND_HL_A", "or", [A, IndHL], 1, 2, "Z000", CPU::or_ind_hl_a),
+    instr!("OR_A_A", "or", [A, A], 1, 1, "Z000", CPU::or_a_a),
+    instr!("CMP_A_B", "cp", [A, B], 1, 1, "Z1HC", CPU::cmp_a_b),
+    instr!("CMP_A_C", "cp", [A, C], 1, 1, "Z1HC", CPU::cmp_a_c),
+    instr!("CMP_A_D", "cp", [A, D], 1, 1, "Z1HC", CPU::cmp_a_d),
+    instr!("CMP_A_E", "cp", [A, E], 1, 1, "Z1HC", CPU::cmp_a_e),
+    instr!("CMP_A_H", "cp", [A, H], 1, 1, "Z1HC", CPU::cmp_a_h),
+    instr!("CMP_A_L", "cp", [A, L], 1, 1, "Z1HC", CPU::cmp_a_l),
+    instr!("CMP_IND_HL_A", "cp", [A, IndHL], 1, 2, "Z1HC", CPU::cmp_ind_hl_a),
+    instr!("CMP_A_A", "cp", [A, A], 1, 1, "Z1HC", CPU::cmp_a_a),
+    instr!("RET_NZ", "ret", [Cond(NotZero)], 1, 5 / 2, "----", CPU::ret_nz),
+    instr!("POP_BC", "pop", [BC], 1, 3, "----", CPU::pop_bc),
+    instr!("JMP_NZ", "jp", [Cond(NotZero), Imm16], 3, 4 / 3, "----", CPU::jump_nz),
+    instr!("JMP_IMM", "jp", [Imm16], 3, 4, "----", CPU::jump_16_bit),
+    instr!("CALL_NZ", "call", [Cond(NotZero), Imm16], 3, 6 / 3, "----", CPU::call_nz),
+    instr!("PUSH_BC", "push", [BC], 1, 4, "----", CPU::push_bc),
+    instr!("ADD_IMM_A", "add", [A, Imm8], 2, 2, "Z0HC", CPU::add_imm_a),
+    instr!("RST_0", "rst", [Vector(0x00)], 1, 4, "----", CPU::reset_00),
+    instr!("RET_Z", "ret", [Cond(Zero)], 1, 5 / 2, "----", CPU::ret_z),
+    instr!("RET", "ret", [], 1, 4, "----", CPU::instr_return),
+    instr!("JMP_Z", "jp", [Cond(Zero), Imm16], 3, 4 / 3, "----", CPU::jump_z),
+    instr!("PREFIX", "prefix", [], 1, 1 / 1, "----", |cpu: &mut CPU<B>| {
         let opcode = cpu.read_from_pc();
-        let instruction = &PREFIXED_INSTRUCTIONS[opcode as usize];
 
-        (instruction.function)(cpu)
+        (CPU::PREFIXED_HANDLERS[opcode as usize])(cpu)
     }),
-    instr!("CALL_Z", CPU::call_z),
-    instr!("CALL", CPU::call, 6),
-    instr!("ADD_IMM_A_WITH_CARRY", CPU::add_imm_a_with_carry, 2),
-    instr!("RST_1", CPU::reset_08, 4),
-    instr!("RET_NC", CPU::ret_nc),
-    instr!("POP_DE", CPU::pop_de, 3),
-    instr!("JMP_NC", CPU::jump_nc),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("CALL_NC", CPU::call_nc),
-    instr!("PUSH_DE", CPU::push_de, 4),
-    instr!("SUB_IMM_A", CPU::sub_imm_a, 2),
-    instr!("RST_2", CPU::reset_10, 4),
-    instr!("RET_C", CPU::ret_c),
-    instr!("RETI", CPU::ret_enable_interrupts, 4),
-    instr!("JMP_C", CPU::jump_c),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("CALL_C", CPU::call_c),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("SUB_IMM_A_WITH_CARRY", CPU::sub_imm_a_with_carry, 2),
-    instr!("RST_3", CPU::reset_18, 4),
-    instr!("STR_IND_A_8BIT", CPU::str_ind_a_8_bit, 3),
-    instr!("POP_HL", CPU::pop_hl, 3),
-    instr!("STR_IND_A_C_8BIT", CPU::str_ind_a_c_8_bit, 2),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("PUSH_HL", CPU::push_hl, 4),
-    instr!("AND_IMM_A", CPU::and_imm_a, 2),
-    instr!("RST_4", CPU::reset_20, 4),
-    instr!("ADD_IMM_SP", CPU::add_imm_sp, 4),
-    instr!("JMP_HL", CPU::jump_hl, 1),
-    instr!("STR_IND_A", CPU::str_ind_a, 4),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("XOR_IMM_A", CPU::xor_imm_a, 2),
-    instr!("RST_5", CPU::reset_28, 4),
-    instr!("LD_IND_A_8BIT", CPU::ld_ind_a_8_bit, 3),
-    instr!("POP_AF", CPU::pop_af, 3),
-    instr!("LD_IND_A_C_8BIT", CPU::ld_ind_a_c_8_bit, 2),
-    instr!("DI", CPU::disable_interrupts, 1),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("PUSH_AF", CPU::push_af, 4),
-    instr!("OR_IMM_A", CPU::or_imm_a, 2),
-    instr!("RST_6", CPU::reset_30, 4),
-    instr!("STR_IMM_SP_HL", CPU::str_imm_sp_hl, 3),
-    instr!("LD_SP_HL", CPU::ld_sp_hl, 2),
-    instr!("LD_IND_A", CPU::ld_ind_a, 4),
-    instr!("EI", CPU::enable_interrupts, 1),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("UNDEF", CPU::no_impl, 0),
-    instr!("CMP_IMM_A", CPU::cmp_imm_a, 2),
-    instr!("RST_7", CPU::reset_38, 4),
-];
-
-const PREFIXED_INSTRUCTIONS: &'static [Instruction] = &[
-    prefixed_instr!("RLC_B", CPU::rotate_left_carry_b),
-    prefixed_instr!("RLC_C", CPU::rotate_left_carry_c),
-    prefixed_instr!("RLC_D", CPU::rotate_left_carry_d),
-    prefixed_instr!("RLC_E", CPU::rotate_left_carry_e),
-    prefixed_instr!("RLC_H", CPU::rotate_left_carry_h),
-    prefixed_instr!("RLC_L", CPU::rotate_left_carry_l),
-    prefixed_instr!("RLC_IND_HL", CPU::rotate_left_carry_ind_hl),
-    prefixed_instr!("RLC_A", CPU::rotate_left_carry_a),
-];
+    instr!("CALL_Z", "call", [Cond(Zero), Imm16], 3, 6 / 3, "----", CPU::call_z),
+    instr!("CALL", "call", [Imm16], 3, 6, "----", CPU::call),
+    instr!("ADD_IMM_A_WITH_CARRY", "adc", [A, Imm8], 2, 2, "Z0HC", CPU::add_imm_a_with_carry),
+    instr!("RST_1", "rst", [Vector(0x08)], 1, 4, "----", CPU::reset_08),
+    instr!("RET_NC", "ret", [Cond(NotCarry)], 1, 5 / 2, "----", CPU::ret_nc),
+    instr!("POP_DE", "pop", [DE], 1, 3, "----", CPU::pop_de),
+    instr!("JMP_NC", "jp", [Cond(NotCarry), Imm16], 3, 4 / 3, "----", CPU::jump_nc),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("CALL_NC", "call", [Cond(NotCarry), Imm16], 3, 6 / 3, "----", CPU::call_nc),
+    instr!("PUSH_DE", "push", [DE], 1, 4, "----", CPU::push_de),
+    instr!("SUB_IMM_A", "sub", [A, Imm8], 2, 2, "Z1HC", CPU::sub_imm_a),
+    instr!("RST_2", "rst", [Vector(0x10)], 1, 4, "----", CPU::reset_10),
+    instr!("RET_C", "ret", [Cond(Carry)], 1, 5 / 2, "----", CPU::ret_c),
+    instr!("RETI", "reti", [], 1, 4, "----", CPU::ret_enable_interrupts),
+    instr!("JMP_C", "jp", [Cond(Carry), Imm16], 3, 4 / 3, "----", CPU::jump_c),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("CALL_C", "call", [Cond(Carry), Imm16], 3, 6 / 3, "----", CPU::call_c),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("SUB_IMM_A_WITH_CARRY", "sbc", [A, Imm8], 2, 2, "Z1HC", CPU::sub_imm_a_with_carry),
+    instr!("RST_3", "rst", [Vector(0x18)], 1, 4, "----", CPU::reset_18),
+    instr!("STR_IND_A_8BIT", "ldh", [IndHighImm8, A], 2, 3, "----", CPU::str_ind_a_8_bit),
+    instr!("POP_HL", "pop", [HL], 1, 3, "----", CPU::pop_hl),
+    instr!("STR_IND_A_C_8BIT", "ldh", [IndHighC, A], 1, 2, "----", CPU::str_ind_a_c_8_bit),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("PUSH_HL", "push", [HL], 1, 4, "----", CPU::push_hl),
+    instr!("AND_IMM_A", "and", [A, Imm8], 2, 2, "Z010", CPU::and_imm_a),
+    instr!("RST_4", "rst", [Vector(0x20)], 1, 4, "----", CPU::reset_20),
+    instr!("ADD_IMM_SP", "add", [SP, SignedImm8], 2, 4, "00HC", CPU::add_imm_sp),
+    instr!("JMP_HL", "jp", [HL], 1, 1, "----", CPU::jump_hl),
+    instr!("STR_IND_A", "ld", [IndImm16, A], 3, 4, "----", CPU::str_ind_a),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("XOR_IMM_A", "xor", [A, Imm8], 2, 2, "Z000", CPU::xor_imm_a),
+    instr!("RST_5", "rst", [Vector(0x28)], 1, 4, "----", CPU::reset_28),
+    instr!("LD_IND_A_8BIT", "ldh", [A, IndHighImm8], 2, 3, "----", CPU::ld_ind_a_8_bit),
+    instr!("POP_AF", "pop", [AF], 1, 3, "ZNHC", CPU::pop_af),
+    instr!("LD_IND_A_C_8BIT", "ldh", [A, IndHighC], 1, 2, "----", CPU::ld_ind_a_c_8_bit),
+    instr!("DI", "di", [], 1, 1, "----", CPU::disable_interrupts),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("PUSH_AF", "push", [AF], 1, 4, "----", CPU::push_af),
+    instr!("OR_IMM_A", "or", [A, Imm8], 2, 2, "Z000", CPU::or_imm_a),
+    instr!("RST_6", "rst", [Vector(0x30)], 1, 4, "----", CPU::reset_30),
+    instr!("STR_IMM_SP_HL", "ld", [HL, SPOffset8], 2, 3, "00HC", CPU::str_imm_sp_hl),
+    instr!("LD_SP_HL", "ld", [SP, HL], 1, 2, "----", CPU::ld_sp_hl),
+    instr!("LD_IND_A", "ld", [A, IndImm16], 3, 4, "----", CPU::ld_ind_a),
+    instr!("EI", "ei", [], 1, 1, "----", CPU::enable_interrupts),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("UNDEF", "db", [], 1, 0, "----", CPU::lock_up),
+    instr!("CMP_IMM_A", "cp", [A, Imm8], 2, 2, "Z1HC", CPU::cmp_imm_a),
+    instr!("RST_7", "rst", [Vector(0x38)], 1, 4, "----", CPU::reset_38),
+]}
+
+// All instructions prefixed with 0xCB, their cycle counts include fetching the prefix
+instruction_table! { PREFIXED_INSTRUCTIONS, PREFIXED_HANDLERS, [
+    instr!("RLC_B", "rlc", [B], 2, 2, "Z00C", CPU::rotate_left_carry_b),
+    instr!("RLC_C", "rlc", [C], 2, 2, "Z00C", CPU::rotate_left_carry_c),
+    instr!("RLC_D", "rlc", [D], 2, 2, "Z00C", CPU::rotate_left_carry_d),
+    instr!("RLC_E", "rlc", [E], 2, 2, "Z00C", CPU::rotate_left_carry_e),
+    instr!("RLC_H", "rlc", [H], 2, 2, "Z00C", CPU::rotate_left_carry_h),
+    instr!("RLC_L", "rlc", [L], 2, 2, "Z00C", CPU::rotate_left_carry_l),
+    instr!("RLC_IND_HL", "rlc", [IndHL], 2, 4, "Z00C", CPU::rotate_left_carry_ind_hl),
+    instr!("RLC_A", "rlc", [A], 2, 2, "Z00C", CPU::rotate_left_carry_a),
+    instr!("RRC_B", "rrc", [B], 2, 2, "Z00C", CPU::rotate_right_carry_b),
+    instr!("RRC_C", "rrc", [C], 2, 2, "Z00C", CPU::rotate_right_carry_c),
+    instr!("RRC_D", "rrc", [D], 2, 2, "Z00C", CPU::rotate_right_carry_d),
+    instr!("RRC_E", "rrc", [E], 2, 2, "Z00C", CPU::rotate_right_carry_e),
+    instr!("RRC_H", "rrc", [H], 2, 2, "Z00C", CPU::rotate_right_carry_h),
+    instr!("RRC_L", "rrc", [L], 2, 2, "Z00C", CPU::rotate_right_carry_l),
+    instr!("RRC_IND_HL", "rrc", [IndHL], 2, 4, "Z00C", CPU::rotate_right_carry_ind_hl),
+    instr!("RRC_A", "rrc", [A], 2, 2, "Z00C", CPU::rotate_right_carry_a),
+    instr!("RL_B", "rl", [B], 2, 2, "Z00C", CPU::rotate_left_through_carry_b),
+    instr!("RL_C", "rl", [C], 2, 2, "Z00C", CPU::rotate_left_through_carry_c),
+    instr!("RL_D", "rl", [D], 2, 2, "Z00C", CPU::rotate_left_through_carry_d),
+    instr!("RL_E", "rl", [E], 2, 2, "Z00C", CPU::rotate_left_through_carry_e),
+    instr!("RL_H", "rl", [H], 2, 2, "Z00C", CPU::rotate_left_through_carry_h),
+    instr!("RL_L", "rl", [L], 2, 2, "Z00C", CPU::rotate_left_through_carry_l),
+    instr!("RL_IND_HL", "rl", [IndHL], 2, 4, "Z00C", CPU::rotate_left_through_carry_ind_hl),
+    instr!("RL_A", "rl", [A], 2, 2, "Z00C", CPU::rotate_left_through_carry_a),
+    instr!("RR_B", "rr", [B], 2, 2, "Z00C", CPU::rotate_right_through_carry_b),
+    instr!("RR_C", "rr", [C], 2, 2, "Z00C", CPU::rotate_right_through_carry_c),
+    instr!("RR_D", "rr", [D], 2, 2, "Z00C", CPU::rotate_right_through_carry_d),
+    instr!("RR_E", "rr", [E], 2, 2, "Z00C", CPU::rotate_right_through_carry_e),
+    instr!("RR_H", "rr", [H], 2, 2, "Z00C", CPU::rotate_right_through_carry_h),
+    instr!("RR_L", "rr", [L], 2, 2, "Z00C", CPU::rotate_right_through_carry_l),
+    instr!("RR_IND_HL", "rr", [IndHL], 2, 4, "Z00C", CPU::rotate_right_through_carry_ind_hl),
+    instr!("RR_A", "rr", [A], 2, 2, "Z00C", CPU::rotate_right_through_carry_a),
+    instr!("SLA_B", "sla", [B], 2, 2, "Z00C", CPU::shift_left_arithmetic_b),
+    instr!("SLA_C", "sla", [C], 2, 2, "Z00C", CPU::shift_left_arithmetic_c),
+    instr!("SLA_D", "sla", [D], 2, 2, "Z00C", CPU::shift_left_arithmetic_d),
+    instr!("SLA_E", "sla", [E], 2, 2, "Z00C", CPU::shift_left_arithmetic_e),
+    instr!("SLA_H", "sla", [H], 2, 2, "Z00C", CPU::shift_left_arithmetic_h),
+    instr!("SLA_L", "sla", [L], 2, 2, "Z00C", CPU::shift_left_arithmetic_l),
+    instr!("SLA_IND_HL", "sla", [IndHL], 2, 4, "Z00C", CPU::shift_left_arithmetic_ind_hl),
+    instr!("SLA_A", "sla", [A], 2, 2, "Z00C", CPU::shift_left_arithmetic_a),
+    instr!("SRA_B", "sra", [B], 2, 2, "Z00C", CPU::shift_right_arithmetic_b),
+    instr!("SRA_C", "sra", [C], 2, 2, "Z00C", CPU::shift_right_arithmetic_c),
+    instr!("SRA_D", "sra", [D], 2, 2, "Z00C", CPU::shift_right_arithmetic_d),
+    instr!("SRA_E", "sra", [E], 2, 2, "Z00C", CPU::shift_right_arithmetic_e),
+    instr!("SRA_H", "sra", [H], 2, 2, "Z00C", CPU::shift_right_arithmetic_h),
+    instr!("SRA_L", "sra", [L], 2, 2, "Z00C", CPU::shift_right_arithmetic_l),
+    instr!("SRA_IND_HL", "sra", [IndHL], 2, 4, "Z00C", CPU::shift_right_arithmetic_ind_hl),
+    instr!("SRA_A", "sra", [A], 2, 2, "Z00C", CPU::shift_right_arithmetic_a),
+    instr!("SWAP_B", "swap", [B], 2, 2, "Z000", CPU::swap_b),
+    instr!("SWAP_C", "swap", [C], 2, 2, "Z000", CPU::swap_c),
+    instr!("SWAP_D", "swap", [D], 2, 2, "Z000", CPU::swap_d),
+    instr!("SWAP_E", "swap", [E], 2, 2, "Z000", CPU::swap_e),
+    instr!("SWAP_H", "swap", [H], 2, 2, "Z000", CPU::swap_h),
+    instr!("SWAP_L", "swap", [L], 2, 2, "Z000", CPU::swap_l),
+    instr!("SWAP_IND_HL", "swap", [IndHL], 2, 4, "Z000", CPU::swap_ind_hl),
+    instr!("SWAP_A", "swap", [A], 2, 2, "Z000", CPU::swap_a),
+    instr!("SRL_B", "srl", [B], 2, 2, "Z00C", CPU::shift_right_logical_b),
+    instr!("SRL_C", "srl", [C], 2, 2, "Z00C", CPU::shift_right_logical_c),
+    instr!("SRL_D", "srl", [D], 2, 2, "Z00C", CPU::shift_right_logical_d),
+    instr!("SRL_E", "srl", [E], 2, 2, "Z00C", CPU::shift_right_logical_e),
+    instr!("SRL_H", "srl", [H], 2, 2, "Z00C", CPU::shift_right_logical_h),
+    instr!("SRL_L", "srl", [L], 2, 2, "Z00C", CPU::shift_right_logical_l),
+    instr!("SRL_IND_HL", "srl", [IndHL], 2, 4, "Z00C", CPU::shift_right_logical_ind_hl),
+    instr!("SRL_A", "srl", [A], 2, 2, "Z00C", CPU::shift_right_logical_a),
+    instr!("BIT_0_B", "bit", [Bit(0), B], 2, 2, "Z01-", CPU::test_bit_0_b),
+    instr!("BIT_0_C", "bit", [Bit(0), C], 2, 2, "Z01-", CPU::test_bit_0_c),
+    instr!("BIT_0_D", "bit", [Bit(0), D], 2, 2, "Z01-", CPU::test_bit_0_d),
+    instr!("BIT_0_E", "bit", [Bit(0), E], 2, 2, "Z01-", CPU::test_bit_0_e),
+    instr!("BIT_0_H", "bit", [Bit(0), H], 2, 2, "Z01-", CPU::test_bit_0_h),
+    instr!("BIT_0_L", "bit", [Bit(0), L], 2, 2, "Z01-", CPU::test_bit_0_l),
+    instr!("BIT_0_IND_HL", "bit", [Bit(0), IndHL], 2, 3, "Z01-", CPU::test_bit_0_ind_hl),
+    instr!("BIT_0_A", "bit", [Bit(0), A], 2, 2, "Z01-", CPU::test_bit_0_a),
+    instr!("BIT_1_B", "bit", [Bit(1), B], 2, 2, "Z01-", CPU::test_bit_1_b),
+    instr!("BIT_1_C", "bit", [Bit(1), C], 2, 2, "Z01-", CPU::test_bit_1_c),
+    instr!("BIT_1_D", "bit", [Bit(1), D], 2, 2, "Z01-", CPU::test_bit_1_d),
+    instr!("BIT_1_E", "bit", [Bit(1), E], 2, 2, "Z01-", CPU::test_bit_1_e),
+    instr!("BIT_1_H", "bit", [Bit(1), H], 2, 2, "Z01-", CPU::test_bit_1_h),
+    instr!("BIT_1_L", "bit", [Bit(1), L], 2, 2, "Z01-", CPU::test_bit_1_l),
+    instr!("BIT_1_IND_HL", "bit", [Bit(1), IndHL], 2, 3, "Z01-", CPU::test_bit_1_ind_hl),
+    instr!("BIT_1_A", "bit", [Bit(1), A], 2, 2, "Z01-", CPU::test_bit_1_a),
+    instr!("BIT_2_B", "bit", [Bit(2), B], 2, 2, "Z01-", CPU::test_bit_2_b),
+    instr!("BIT_2_C", "bit", [Bit(2), C], 2, 2, "Z01-", CPU::test_bit_2_c),
+    instr!("BIT_2_D", "bit", [Bit(2), D], 2, 2, "Z01-", CPU::test_bit_2_d),
+    instr!("BIT_2_E", "bit", [Bit(2), E], 2, 2, "Z01-", CPU::test_bit_2_e),
+    instr!("BIT_2_H", "bit", [Bit(2), H], 2, 2, "Z01-", CPU::test_bit_2_h),
+    instr!("BIT_2_L", "bit", [Bit(2), L], 2, 2, "Z01-", CPU::test_bit_2_l),
+    instr!("BIT_2_IND_HL", "bit", [Bit(2), IndHL], 2, 3, "Z01-", CPU::test_bit_2_ind_hl),
+    instr!("BIT_2_A", "bit", [Bit(2), A], 2, 2, "Z01-", CPU::test_bit_2_a),
+    instr!("BIT_3_B", "bit", [Bit(3), B], 2, 2, "Z01-", CPU::test_bit_3_b),
+    instr!("BIT_3_C", "bit", [Bit(3), C], 2, 2, "Z01-", CPU::test_bit_3_c),
+    instr!("BIT_3_D", "bit", [Bit(3), D], 2, 2, "Z01-", CPU::test_bit_3_d),
+    instr!("BIT_3_E", "bit", [Bit(3), E], 2, 2, "Z01-", CPU::test_bit_3_e),
+    instr!("BIT_3_H", "bit", [Bit(3), H], 2, 2, "Z01-", CPU::test_bit_3_h),
+    instr!("BIT_3_L", "bit", [Bit(3), L], 2, 2, "Z01-", CPU::test_bit_3_l),
+    instr!("BIT_3_IND_HL", "bit", [Bit(3), IndHL], 2, 3, "Z01-", CPU::test_bit_3_ind_hl),
+    instr!("BIT_3_A", "bit", [Bit(3), A], 2, 2, "Z01-", CPU::test_bit_3_a),
+    instr!("BIT_4_B", "bit", [Bit(4), B], 2, 2, "Z01-", CPU::test_bit_4_b),
+    instr!("BIT_4_C", "bit", [Bit(4), C], 2, 2, "Z01-", CPU::test_bit_4_c),
+    instr!("BIT_4_D", "bit", [Bit(4), D], 2, 2, "Z01-", CPU::test_bit_4_d),
+    instr!("BIT_4_E", "bit", [Bit(4), E], 2, 2, "Z01-", CPU::test_bit_4_e),
+    instr!("BIT_4_H", "bit", [Bit(4), H], 2, 2, "Z01-", CPU::test_bit_4_h),
+    instr!("BIT_4_L", "bit", [Bit(4), L], 2, 2, "Z01-", CPU::test_bit_4_l),
+    instr!("BIT_4_IND_HL", "bit", [Bit(4), IndHL], 2, 3, "Z01-", CPU::test_bit_4_ind_hl),
+    instr!("BIT_4_A", "bit", [Bit(4), A], 2, 2, "Z01-", CPU::test_bit_4_a),
+    instr!("BIT_5_B", "bit", [Bit(5), B], 2, 2, "Z01-", CPU::test_bit_5_b),
+    instr!("BIT_5_C", "bit", [Bit(5), C], 2, 2, "Z01-", CPU::test_bit_5_c),
+    instr!("BIT_5_D", "bit", [Bit(5), D], 2, 2, "Z01-", CPU::test_bit_5_d),
+    instr!("BIT_5_E", "bit", [Bit(5), E], 2, 2, "Z01-", CPU::test_bit_5_e),
+    instr!("BIT_5_H", "bit", [Bit(5), H], 2, 2, "Z01-", CPU::test_bit_5_h),
+    instr!("BIT_5_L", "bit", [Bit(5), L], 2, 2, "Z01-", CPU::test_bit_5_l),
+    instr!("BIT_5_IND_HL", "bit", [Bit(5), IndHL], 2, 3, "Z01-", CPU::test_bit_5_ind_hl),
+    instr!("BIT_5_A", "bit", [Bit(5), A], 2, 2, "Z01-", CPU::test_bit_5_a),
+    instr!("BIT_6_B", "bit", [Bit(6), B], 2, 2, "Z01-", CPU::test_bit_6_b),
+    instr!("BIT_6_C", "bit", [Bit(6), C], 2, 2, "Z01-", CPU::test_bit_6_c),
+    instr!("BIT_6_D", "bit", [Bit(6), D], 2, 2, "Z01-", CPU::test_bit_6_d),
+    instr!("BIT_6_E", "bit", [Bit(6), E], 2, 2, "Z01-", CPU::test_bit_6_e),
+    instr!("BIT_6_H", "bit", [Bit(6), H], 2, 2, "Z01-", CPU::test_bit_6_h),
+    instr!("BIT_6_L", "bit", [Bit(6), L], 2, 2, "Z01-", CPU::test_bit_6_l),
+    instr!("BIT_6_IND_HL", "bit", [Bit(6), IndHL], 2, 3, "Z01-", CPU::test_bit_6_ind_hl),
+    instr!("BIT_6_A", "bit", [Bit(6), A], 2, 2, "Z01-", CPU::test_bit_6_a),
+    instr!("BIT_7_B", "bit", [Bit(7), B], 2, 2, "Z01-", CPU::test_bit_7_b),
+    instr!("BIT_7_C", "bit", [Bit(7), C], 2, 2, "Z01-", CPU::test_bit_7_c),
+    instr!("BIT_7_D", "bit", [Bit(7), D], 2, 2, "Z01-", CPU::test_bit_7_d),
+    instr!("BIT_7_E", "bit", [Bit(7), E], 2, 2, "Z01-", CPU::test_bit_7_e),
+    instr!("BIT_7_H", "bit", [Bit(7), H], 2, 2, "Z01-", CPU::test_bit_7_h),
+    instr!("BIT_7_L", "bit", [Bit(7), L], 2, 2, "Z01-", CPU::test_bit_7_l),
+    instr!("BIT_7_IND_HL", "bit", [Bit(7), IndHL], 2, 3, "Z01-", CPU::test_bit_7_ind_hl),
+    instr!("BIT_7_A", "bit", [Bit(7), A], 2, 2, "Z01-", CPU::test_bit_7_a),
+    instr!("RES_0_B", "res", [Bit(0), B], 2, 2, "----", CPU::reset_bit_0_b),
+    instr!("RES_0_C", "res", [Bit(0), C], 2, 2, "----", CPU::reset_bit_0_c),
+    instr!("RES_0_D", "res", [Bit(0), D], 2, 2, "----", CPU::reset_bit_0_d),
+    instr!("RES_0_E", "res", [Bit(0), E], 2, 2, "----", CPU::reset_bit_0_e),
+    instr!("RES_0_H", "res", [Bit(0), H], 2, 2, "----", CPU::reset_bit_0_h),
+    instr!("RES_0_L", "res", [Bit(0), L], 2, 2, "----", CPU::reset_bit_0_l),
+    instr!("RES_0_IND_HL", "res", [Bit(0), IndHL], 2, 4, "----", CPU::reset_bit_0_ind_hl),
+    instr!("RES_0_A", "res", [Bit(0), A], 2, 2, "----", CPU::reset_bit_0_a),
+    instr!("RES_1_B", "res", [Bit(1), B], 2, 2, "----", CPU::reset_bit_1_b),
+    instr!("RES_1_C", "res", [Bit(1), C], 2, 2, "----", CPU::reset_bit_1_c),
+    instr!("RES_1_D", "res", [Bit(1), D], 2, 2, "----", CPU::reset_bit_1_d),
+    instr!("RES_1_E", "res", [Bit(1), E], 2, 2, "----", CPU::reset_bit_1_e),
+    instr!("RES_1_H", "res", [Bit(1), H], 2, 2, "----", CPU::reset_bit_1_h),
+    instr!("RES_1_L", "res", [Bit(1), L], 2, 2, "----", CPU::reset_bit_1_l),
+    instr!("RES_1_IND_HL", "res", [Bit(1), IndHL], 2, 4, "----", CPU::reset_bit_1_ind_hl),
+    instr!("RES_1_A", "res", [Bit(1), A], 2, 2, "----", CPU::reset_bit_1_a),
+    instr!("RES_2_B", "res", [Bit(2), B], 2, 2, "----", CPU::reset_bit_2_b),
+    instr!("RES_2_C", "res", [Bit(2), C], 2, 2, "----", CPU::reset_bit_2_c),
+    instr!("RES_2_D", "res", [Bit(2), D], 2, 2, "----", CPU::reset_bit_2_d),
+    instr!("RES_2_E", "res", [Bit(2), E], 2, 2, "----", CPU::reset_bit_2_e),
+    instr!("RES_2_H", "res", [Bit(2), H], 2, 2, "----", CPU::reset_bit_2_h),
+    instr!("RES_2_L", "res", [Bit(2), L], 2, 2, "----", CPU::reset_bit_2_l),
+    instr!("RES_2_IND_HL", "res", [Bit(2), IndHL], 2, 4, "----", CPU::reset_bit_2_ind_hl),
+    instr!("RES_2_A", "res", [Bit(2), A], 2, 2, "----", CPU::reset_bit_2_a),
+    instr!("RES_3_B", "res", [Bit(3), B], 2, 2, "----", CPU::reset_bit_3_b),
+    instr!("RES_3_C", "res", [Bit(3), C], 2, 2, "----", CPU::reset_bit_3_c),
+    instr!("RES_3_D", "res", [Bit(3), D], 2, 2, "----", CPU::reset_bit_3_d),
+    instr!("RES_3_E", "res", [Bit(3), E], 2, 2, "----", CPU::reset_bit_3_e),
+    instr!("RES_3_H", "res", [Bit(3), H], 2, 2, "----", CPU::reset_bit_3_h),
+    instr!("RES_3_L", "res", [Bit(3), L], 2, 2, "----", CPU::reset_bit_3_l),
+    instr!("RES_3_IND_HL", "res", [Bit(3), IndHL], 2, 4, "----", CPU::reset_bit_3_ind_hl),
+    instr!("RES_3_A", "res", [Bit(3), A], 2, 2, "----", CPU::reset_bit_3_a),
+    instr!("RES_4_B", "res", [Bit(4), B], 2, 2, "----", CPU::reset_bit_4_b),
+    instr!("RES_4_C", "res", [Bit(4), C], 2, 2, "----", CPU::reset_bit_4_c),
+    instr!("RES_4_D", "res", [Bit(4), D], 2, 2, "----", CPU::reset_bit_4_d),
+    instr!("RES_4_E", "res", [Bit(4), E], 2, 2, "----", CPU::reset_bit_4_e),
+    instr!("RES_4_H", "res", [Bit(4), H], 2, 2, "----", CPU::reset_bit_4_h),
+    instr!("RES_4_L", "res", [Bit(4), L], 2, 2, "----", CPU::reset_bit_4_l),
+    instr!("RES_4_IND_HL", "res", [Bit(4), IndHL], 2, 4, "----", CPU::reset_bit_4_ind_hl),
+    instr!("RES_4_A", "res", [Bit(4), A], 2, 2, "----", CPU::reset_bit_4_a),
+    instr!("RES_5_B", "res", [Bit(5), B], 2, 2, "----", CPU::reset_bit_5_b),
+    instr!("RES_5_C", "res", [Bit(5), C], 2, 2, "----", CPU::reset_bit_5_c),
+    instr!("RES_5_D", "res", [Bit(5), D], 2, 2, "----", CPU::reset_bit_5_d),
+    instr!("RES_5_E", "res", [Bit(5), E], 2, 2, "----", CPU::reset_bit_5_e),
+    instr!("RES_5_H", "res", [Bit(5), H], 2, 2, "----", CPU::reset_bit_5_h),
+    instr!("RES_5_L", "res", [Bit(5), L], 2, 2, "----", CPU::reset_bit_5_l),
+    instr!("RES_5_IND_HL", "res", [Bit(5), IndHL], 2, 4, "----", CPU::reset_bit_5_ind_hl),
+    instr!("RES_5_A", "res", [Bit(5), A], 2, 2, "----", CPU::reset_bit_5_a),
+    instr!("RES_6_B", "res", [Bit(6), B], 2, 2, "----", CPU::reset_bit_6_b),
+    instr!("RES_6_C", "res", [Bit(6), C], 2, 2, "----", CPU::reset_bit_6_c),
+    instr!("RES_6_D", "res", [Bit(6), D], 2, 2, "----", CPU::reset_bit_6_d),
+    instr!("RES_6_E", "res", [Bit(6), E], 2, 2, "----", CPU::reset_bit_6_e),
+    instr!("RES_6_H", "res", [Bit(6), H], 2, 2, "----", CPU::reset_bit_6_h),
+    instr!("RES_6_L", "res", [Bit(6), L], 2, 2, "----", CPU::reset_bit_6_l),
+    instr!("RES_6_IND_HL", "res", [Bit(6), IndHL], 2, 4, "----", CPU::reset_bit_6_ind_hl),
+    instr!("RES_6_A", "res", [Bit(6), A], 2, 2, "----", CPU::reset_bit_6_a),
+    instr!("RES_7_B", "res", [Bit(7), B], 2, 2, "----", CPU::reset_bit_7_b),
+    instr!("RES_7_C", "res", [Bit(7), C], 2, 2, "----", CPU::reset_bit_7_c),
+    instr!("RES_7_D", "res", [Bit(7), D], 2, 2, "----", CPU::reset_bit_7_d),
+    instr!("RES_7_E", "res", [Bit(7), E], 2, 2, "----", CPU::reset_bit_7_e),
+    instr!("RES_7_H", "res", [Bit(7), H], 2, 2, "----", CPU::reset_bit_7_h),
+    instr!("RES_7_L", "res", [Bit(7), L], 2, 2, "----", CPU::reset_bit_7_l),
+    instr!("RES_7_IND_HL", "res", [Bit(7), IndHL], 2, 4, "----", CPU::reset_bit_7_ind_hl),
+    instr!("RES_7_A", "res", [Bit(7), A], 2, 2, "----", CPU::reset_bit_7_a),
+    instr!("SET_0_B", "set", [Bit(0), B], 2, 2, "----", CPU::set_bit_0_b),
+    instr!("SET_0_C", "set", [Bit(0), C], 2, 2, "----", CPU::set_bit_0_c),
+    instr!("SET_0_D", "set", [Bit(0), D], 2, 2, "----", CPU::set_bit_0_d),
+    instr!("SET_0_E", "set", [Bit(0), E], 2, 2, "----", CPU::set_bit_0_e),
+    instr!("SET_0_H", "set", [Bit(0), H], 2, 2, "----", CPU::set_bit_0_h),
+    instr!("SET_0_L", "set", [Bit(0), L], 2, 2, "----", CPU::set_bit_0_l),
+    instr!("SET_0_IND_HL", "set", [Bit(0), IndHL], 2, 4, "----", CPU::set_bit_0_ind_hl),
+    instr!("SET_0_A", "set", [Bit(0), A], 2, 2, "----", CPU::set_bit_0_a),
+    instr!("SET_1_B", "set", [Bit(1), B], 2, 2, "----", CPU::set_bit_1_b),
+    instr!("SET_1_C", "set", [Bit(1), C], 2, 2, "----", CPU::set_bit_1_c),
+    instr!("SET_1_D", "set", [Bit(1), D], 2, 2, "----", CPU::set_bit_1_d),
+    instr!("SET_1_E", "set", [Bit(1), E], 2, 2, "----", CPU::set_bit_1_e),
+    instr!("SET_1_H", "set", [Bit(1), H], 2, 2, "----", CPU::set_bit_1_h),
+    instr!("SET_1_L", "set", [Bit(1), L], 2, 2, "----", CPU::set_bit_1_l),
+    instr!("SET_1_IND_HL", "set", [Bit(1), IndHL], 2, 4, "----", CPU::set_bit_1_ind_hl),
+    instr!("SET_1_A", "set", [Bit(1), A], 2, 2, "----", CPU::set_bit_1_a),
+    instr!("SET_2_B", "set", [Bit(2), B], 2, 2, "----", CPU::set_bit_2_b),
+    instr!("SET_2_C", "set", [Bit(2), C], 2, 2, "----", CPU::set_bit_2_c),
+    instr!("SET_2_D", "set", [Bit(2), D], 2, 2, "----", CPU::set_bit_2_d),
+    instr!("SET_2_E", "set", [Bit(2), E], 2, 2, "----", CPU::set_bit_2_e),
+    instr!("SET_2_H", "set", [Bit(2), H], 2, 2, "----", CPU::set_bit_2_h),
+    instr!("SET_2_L", "set", [Bit(2), L], 2, 2, "----", CPU::set_bit_2_l),
+    instr!("SET_2_IND_HL", "set", [Bit(2), IndHL], 2, 4, "----", CPU::set_bit_2_ind_hl),
+    instr!("SET_2_A", "set", [Bit(2), A], 2, 2, "----", CPU::set_bit_2_a),
+    instr!("SET_3_B", "set", [Bit(3), B], 2, 2, "----", CPU::set_bit_3_b),
+    instr!("SET_3_C", "set", [Bit(3), C], 2, 2, "----", CPU::set_bit_3_c),
+    instr!("SET_3_D", "set", [Bit(3), D], 2, 2, "----", CPU::set_bit_3_d),
+    instr!("SET_3_E", "set", [Bit(3), E], 2, 2, "----", CPU::set_bit_3_e),
+    instr!("SET_3_H", "set", [Bit(3), H], 2, 2, "----", CPU::set_bit_3_h),
+    instr!("SET_3_L", "set", [Bit(3), L], 2, 2, "----", CPU::set_bit_3_l),
+    instr!("SET_3_IND_HL", "set", [Bit(3), IndHL], 2, 4, "----", CPU::set_bit_3_ind_hl),
+    instr!("SET_3_A", "set", [Bit(3), A], 2, 2, "----", CPU::set_bit_3_a),
+    instr!("SET_4_B", "set", [Bit(4), B], 2, 2, "----", CPU::set_bit_4_b),
+    instr!("SET_4_C", "set", [Bit(4), C], 2, 2, "----", CPU::set_bit_4_c),
+    instr!("SET_4_D", "set", [Bit(4), D], 2, 2, "----", CPU::set_bit_4_d),
+    instr!("SET_4_E", "set", [Bit(4), E], 2, 2, "----", CPU::set_bit_4_e),
+    instr!("SET_4_H", "set", [Bit(4), H], 2, 2, "----", CPU::set_bit_4_h),
+    instr!("SET_4_L", "set", [Bit(4), L], 2, 2, "----", CPU::set_bit_4_l),
+    instr!("SET_4_IND_HL", "set", [Bit(4), IndHL], 2, 4, "----", CPU::set_bit_4_ind_hl),
+    instr!("SET_4_A", "set", [Bit(4), A], 2, 2, "----", CPU::set_bit_4_a),
+    instr!("SET_5_B", "set", [Bit(5), B], 2, 2, "----", CPU::set_bit_5_b),
+    instr!("SET_5_C", "set", [Bit(5), C], 2, 2, "----", CPU::set_bit_5_c),
+    instr!("SET_5_D", "set", [Bit(5), D], 2, 2, "----", CPU::set_bit_5_d),
+    instr!("SET_5_E", "set", [Bit(5), E], 2, 2, "----", CPU::set_bit_5_e),
+    instr!("SET_5_H", "set", [Bit(5), H], 2, 2, "----", CPU::set_bit_5_h),
+    instr!("SET_5_L", "set", [Bit(5), L], 2, 2, "----", CPU::set_bit_5_l),
+    instr!("SET_5_IND_HL", "set", [Bit(5), IndHL], 2, 4, "----", CPU::set_bit_5_ind_hl),
+    instr!("SET_5_A", "set", [Bit(5), A], 2, 2, "----", CPU::set_bit_5_a),
+    instr!("SET_6_B", "set", [Bit(6), B], 2, 2, "----", CPU::set_bit_6_b),
+    instr!("SET_6_C", "set", [Bit(6), C], 2, 2, "----", CPU::set_bit_6_c),
+    instr!("SET_6_D", "set", [Bit(6), D], 2, 2, "----", CPU::set_bit_6_d),
+    instr!("SET_6_E", "set", [Bit(6), E], 2, 2, "----", CPU::set_bit_6_e),
+    instr!("SET_6_H", "set", [Bit(6), H], 2, 2, "----", CPU::set_bit_6_h),
+    instr!("SET_6_L", "set", [Bit(6), L], 2, 2, "----", CPU::set_bit_6_l),
+    instr!("SET_6_IND_HL", "set", [Bit(6), IndHL], 2, 4, "----", CPU::set_bit_6_ind_hl),
+    instr!("SET_6_A", "set", [Bit(6), A], 2, 2, "----", CPU::set_bit_6_a),
+    instr!("SET_7_B", "set", [Bit(7), B], 2, 2, "----", CPU::set_bit_7_b),
+    instr!("SET_7_C", "set", [Bit(7), C], 2, 2, "----", CPU::set_bit_7_c),
+    instr!("SET_7_D", "set", [Bit(7), D], 2, 2, "----", CPU::set_bit_7_d),
+    instr!("SET_7_E", "set", [Bit(7), E], 2, 2, "----", CPU::set_bit_7_e),
+    instr!("SET_7_H", "set", [Bit(7), H], 2, 2, "----", CPU::set_bit_7_h),
+    instr!("SET_7_L", "set", [Bit(7), L], 2, 2, "----", CPU::set_bit_7_l),
+    instr!("SET_7_IND_HL", "set", [Bit(7), IndHL], 2, 4, "----", CPU::set_bit_7_ind_hl),
+    instr!("SET_7_A", "set", [Bit(7), A], 2, 2, "----", CPU::set_bit_7_a),
+]}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_cover_every_opcode() {
+        assert_eq!(INSTRUCTIONS.len(), 256);
+        assert_eq!(PREFIXED_INSTRUCTIONS.len(), 256);
+        assert_eq!(INSTRUCTIONS[0xCB].name, "PREFIX");
+        assert_eq!(INSTRUCTIONS[0xFF].name, "RST_7");
+    }
+
+    #[test]
+    fn test_opcodes_line_up_after_stop() {
+        // A duplicate entry here once shifted every opcode above 0x11 by one
+        assert_eq!(INSTRUCTIONS[0x10].name, "STOP");
+        assert_eq!(INSTRUCTIONS[0x11].name, "LD_IMM_DE");
+        assert_eq!(INSTRUCTIONS[0x12].name, "STR_IND_DE_A");
+        assert_eq!(INSTRUCTIONS[0x13].name, "INC_DE");
+        assert_eq!(INSTRUCTIONS[0x1A].name, "LD_IND_DE_A");
+    }
+
+    #[test]
+    fn test_lengths_match_operands() {
+        for (opcode, instruction) in INSTRUCTIONS.iter().enumerate() {
+            let immediates: u8 = instruction
+                .operands
+                .iter()
+                .map(|operand| match operand {
+                    Imm16 | IndImm16 => 2,
+                    Imm8 | SignedImm8 | IndHighImm8 | Offset8 | SPOffset8 => 1,
+                    _ => 0,
+                })
+                .sum();
+
+            // STOP is followed by a padding byte
+            let padding = (instruction.name == "STOP") as u8;
+
+            assert_eq!(
+                instruction.length,
+                1 + immediates + padding,
+                "{:#04X}",
+                opcode
+            );
+        }
+
+        assert!(
+            PREFIXED_INSTRUCTIONS
+                .iter()
+                .all(|instruction| instruction.length == 2)
+        );
+    }
+
+    #[test]
+    fn test_conditional_metadata() {
+        let jump = &INSTRUCTIONS[0xC2]; // JP NZ, n16
+
+        assert_eq!(jump.operands, &[Cond(NotZero), Imm16]);
+        assert_eq!(jump.cycles, 4);
+        assert_eq!(jump.cycles_not_taken, 3);
+        assert_eq!(jump.flags.zero, FlagEffect::Unaffected);
+
+        let inc = &INSTRUCTIONS[0x34]; // INC (HL)
+
+        assert_eq!(inc.flags.zero, FlagEffect::Affected);
+        assert_eq!(inc.flags.sub, FlagEffect::Reset);
+        assert_eq!(inc.flags.carry, FlagEffect::Unaffected);
+    }
+
+    #[test]
+    fn test_prefixed_ind_hl_cycles() {
+        let mut cpu = crate::cpu::test_helpers::make_cpu();
+        cpu.registers.set_hl(0xC000);
+
+        let handlers = &CPU::PREFIXED_HANDLERS;
+
+        assert_eq!((handlers[0x06])(&mut cpu), 4); // RLC (HL)
+        assert_eq!((handlers[0x46])(&mut cpu), 3); // BIT 0, (HL)
+        assert_eq!((handlers[0xFE])(&mut cpu), 4); // SET 7, (HL)
+        assert_eq!((handlers[0x37])(&mut cpu), 2); // SWAP A
+    }
+}
@@ -1,25 +1,136 @@
-use super::CPU;
+use super::utils::*;
+use super::{CPU, RunState};
+use crate::bus::{Bus, IE_ADDR, IF_ADDR, Interrupt};
 
-impl CPU {
+impl<B: Bus> CPU<B> {
+    // EI only takes effect after the instruction that follows it
     pub(super) fn enable_interrupts(&mut self) {
-        self.bus.borrow_mut().enable_interrupts();
+        self.run_state = RunState::EnablingInterrupts;
     }
 
     pub(super) fn disable_interrupts(&mut self) {
-        self.bus.borrow_mut().disable_interrupts();
+        self.ime = false;
+
+        if self.run_state == RunState::EnablingInterrupts {
+            self.run_state = RunState::Running;
+        }
+    }
+
+    // Interrupts that are both requested (IF) and enabled (IE), regardless of IME
+    pub(super) fn pending_interrupts(&self) -> u8 {
+        self.peek(IE_ADDR) & self.peek(IF_ADDR) & 0x1F
+    }
+
+    /*
+        Interrupt dispatch takes 5 M-cycles: two wait states, two stack pushes and the jump.
+        The interrupt to service is only chosen after the high byte of PC has been pushed,
+        so a push that overwrites IE can cancel the dispatch, in which case PC ends up at 0x0000.
+    */
+    pub(super) fn service_interrupt(&mut self) {
+        self.ime = false;
+
+        let pc = self.registers.pc;
+
+        self.cycle();
+        self.cycle();
+
+        wrapping_sub(&mut self.registers.sp, 1);
+        self.write(self.registers.sp, get_high_byte(pc));
+
+        let interrupt = Interrupt::highest_priority(self.pending_interrupts());
+
+        wrapping_sub(&mut self.registers.sp, 1);
+        self.write(self.registers.sp, get_low_byte(pc));
+
+        match interrupt {
+            Some(interrupt) => {
+                let flags = self.peek(IF_ADDR);
+                self.poke(IF_ADDR, flags & !interrupt.mask());
+
+                self.jump_to_address(interrupt.vector());
+            }
+            None => self.jump_to_address(0x0000),
+        }
+
+        self.cycle();
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::bus::{Bus, IE_ADDR, IF_ADDR, Interrupt};
+    use crate::cpu::RunState;
     use crate::cpu::test_helpers::make_cpu;
 
     #[test]
-    fn test_enable_interrupts() {
+    fn test_enable_interrupts_is_delayed() {
         let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000; // NOP, NOP
 
         cpu.enable_interrupts();
+        assert!(!cpu.ime);
+        assert_eq!(cpu.run_state(), RunState::EnablingInterrupts);
+
+        cpu.tick();
+
+        assert!(cpu.ime);
+        assert_eq!(cpu.run_state(), RunState::Running);
+    }
+
+    #[test]
+    fn test_disable_interrupts_cancels_pending_enable() {
+        let mut cpu = make_cpu();
+
+        cpu.enable_interrupts();
+        cpu.disable_interrupts();
+        cpu.tick();
+
+        assert!(!cpu.ime)
+    }
+
+    #[test]
+    fn test_dispatch_highest_priority() {
+        let mut cpu = make_cpu();
+        cpu.ime = true;
+        cpu.registers.pc = 0x1234;
+        cpu.registers.sp = 0xFFFE;
+        cpu.bus.write(IE_ADDR, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::Joypad);
+        cpu.bus.request_interrupt(Interrupt::Timer);
+
+        cpu.tick();
+
+        assert_eq!(cpu.registers.pc, 0x50);
+        assert_eq!(cpu.registers.sp, 0xFFFC);
+        assert_eq!(cpu.peek(0xFFFC), 0x34);
+        assert_eq!(cpu.peek(0xFFFD), 0x12);
+        assert_eq!(cpu.peek(IF_ADDR), Interrupt::Joypad.mask());
+        assert_eq!(cpu.cycles, 5);
+        assert!(!cpu.ime);
+    }
+
+    #[test]
+    fn test_no_dispatch_when_disabled_in_ie() {
+        let mut cpu = make_cpu();
+        cpu.ime = true;
+        cpu.registers.pc = 0xC000;
+        cpu.bus.request_interrupt(Interrupt::VBlank);
+
+        cpu.tick();
+
+        assert_eq!(cpu.registers.pc, 0xC001);
+    }
+
+    #[test]
+    fn test_reti_enables_immediately() {
+        let mut cpu = make_cpu();
+        cpu.registers.sp = 0xFFFC;
+        cpu.write(0xFFFC, 0x00);
+        cpu.write(0xFFFD, 0xC0);
+
+        cpu.ret_enable_interrupts();
 
-        assert_eq!(cpu.bus.borrow().interrupts_enabled(), true)
+        assert!(cpu.ime);
+        assert_eq!(cpu.registers.pc, 0xC000);
     }
 }
@@ -1,6 +1,7 @@
 use super::CPU;
 use super::registers::Registers;
 use super::utils::*;
+use crate::bus::Bus;
 use paste::paste;
 
 /*
@@ -161,7 +162,7 @@ macro_rules! str_indirect_contents {
     };
 }
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     // Load immediate value to 16 bit register pairs
     fn ld_imm_16_bit(&mut self, set_fn: fn(&mut Registers, u16)) {
         let low_byte = self.read_from_pc();
@@ -200,10 +201,10 @@ impl CPU {
     // Load indirect value provided by register pair to 8 bit register
     ld_ind!(bc, a);
     ld_ind!(de, a);
-    ld_ind!(hl, a, "add", |cpu: &mut CPU| {
+    ld_ind!(hl, a, "add", |cpu: &mut CPU<B>| {
         cpu.increment_hl();
     });
-    ld_ind!(hl, a, "sub", |cpu: &mut CPU| {
+    ld_ind!(hl, a, "sub", |cpu: &mut CPU<B>| {
         cpu.decrement_hl();
     });
 
@@ -231,10 +232,10 @@ impl CPU {
     // Store register in address provided by indirect register memory
     str_ind!(bc, a);
     str_ind!(de, a);
-    str_ind!(hl, a, "add", |cpu: &mut CPU| {
+    str_ind!(hl, a, "add", |cpu: &mut CPU<B>| {
         cpu.increment_hl();
     });
-    str_ind!(hl, a, "sub", |cpu: &mut CPU| {
+    str_ind!(hl, a, "sub", |cpu: &mut CPU<B>| {
         cpu.decrement_hl();
     });
 
@@ -1,55 +1,203 @@
 mod arithmetic;
+pub mod assembler;
 mod bitwise;
+mod block_cache;
+mod boot;
 mod branching;
 mod control;
-mod instructions;
+pub mod disassembler;
+pub mod instructions;
 mod interrupts;
 mod memory;
 mod prefixed_instrs;
 mod registers;
+#[cfg(test)]
+mod sm83_tests;
 mod stack;
+mod state;
+#[cfg(test)]
 mod test_helpers;
+mod trace;
 mod utils;
 
-use std::{cell::RefCell, rc::Rc};
+use std::io::Write;
 
-use crate::bus::{Bus, SystemBus};
-use instructions::*;
+use crate::bus::Bus;
+use block_cache::BlockCache;
+pub use control::IllegalOpcode;
+pub use registers::FlagRegister;
 use registers::Registers;
+pub use state::{CpuState, Register};
 
 /*
-    Future consideration for the CPU struct:
-    When we get to interrupts / CPU state , can we make use of the type state pattern?
-    Might not be applicable, but I can see it being useful when dealing with halted states,
-    Interrupts being enabled / disabled, etc.
+    What the CPU is doing between instructions.
+    This was going to be type state, but HALT, STOP and illegal opcodes are only discovered
+    while executing, and the emulator has to keep ticking a single CPU through all of them,
+    so the state is a plain enum that tick() matches on instead.
 */
-pub struct CPU {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    EnablingInterrupts, // EI was executed, IME turns on before the next instruction
+    Halted,             // Waiting for any interrupt to become pending
+    Stopped,            // Waiting for a button press, with the system clock stopped
+    Locked(IllegalOpcode),
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct CPU<B: Bus> {
     registers: Registers,
-    cycles: u32,
-    bus: Rc<RefCell<dyn Bus>>,
+    cycles: u64, // Total M-cycles elapsed
+    ime: bool,   // Interrupt master enable
+    run_state: RunState,
+    halt_bug: bool,                     // The next opcode fetch fails to increment PC
+    trace: Option<Box<dyn Write>>,      // Opt-in per instruction log, see trace.rs
+    block_cache: Option<BlockCache<B>>, // Opt-in decoded code cache, see block_cache.rs
+    bus: B,
 }
 
-impl CPU {
-    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Self {
+impl<B: Bus> CPU<B> {
+    pub fn new(bus: B) -> Self {
         Self {
             registers: Registers::new(),
             cycles: 0,
-            bus: bus,
+            ime: false,
+            run_state: RunState::Running,
+            halt_bug: false,
+            trace: None,
+            block_cache: None,
+            bus,
         }
     }
 
     pub fn tick(&mut self) {
-        let opcode = self.read_from_pc();
+        match self.run_state {
+            RunState::Locked(_) => {
+                // The CPU never fetches again, but the rest of the machine keeps running
+                self.cycle();
+                return;
+            }
+            RunState::Stopped => {
+                if !self.joypad_pressed() {
+                    // The system clock is halted, so nothing else on the bus advances
+                    self.cycles += 1;
+                    return;
+                }
+
+                self.run_state = RunState::Running;
+            }
+            RunState::Halted => {
+                if self.pending_interrupts() == 0 {
+                    self.cycle();
+                    return;
+                }
+
+                // Any pending interrupt wakes the CPU, even when IME is off
+                self.run_state = RunState::Running;
+            }
+            RunState::Running | RunState::EnablingInterrupts => {}
+        }
+
+        if self.ime && self.pending_interrupts() != 0 {
+            self.service_interrupt();
+            return;
+        }
+
+        if self.run_state == RunState::EnablingInterrupts {
+            self.run_state = RunState::Running;
+            self.ime = true;
+        }
+
+        self.trace_instruction();
+
+        // The HALT bug repeats a fetch, which decoded blocks don't model
+        if self.block_cache.is_some() && !self.halt_bug {
+            self.run_cached_instruction();
+        } else {
+            self.run_instruction();
+        }
+    }
+
+    // Fetches and executes the instruction at PC, spending every M-cycle it takes
+    fn run_instruction(&mut self) {
+        let start = self.cycles;
+
+        let opcode = self.fetch();
+
+        let cycles = self.execute(opcode);
+
+        self.finish_instruction(start, cycles);
+    }
+
+    // Whatever the instruction didn't spend on memory accesses went to internal operations
+    fn finish_instruction(&mut self, start: u64, cycles: u32) {
+        while self.cycles - start < cycles as u64 {
+            self.cycle();
+        }
+    }
+
+    pub fn run_state(&self) -> RunState {
+        self.run_state
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub(crate) fn bus(&self) -> &B {
+        &self.bus
+    }
+
+    // For input and configuring peripherals, which must not change memory or switch banks
+    pub(crate) fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+
+    // For changing memory behind the CPU's back (e.g. loading a save), which drops decoded blocks
+    pub(crate) fn memory_mut(&mut self) -> &mut B {
+        if let Some(cache) = &mut self.block_cache {
+            cache.clear();
+        }
+
+        &mut self.bus
+    }
+
+    // Advances the rest of the machine by one M-cycle
+    fn cycle(&mut self) {
+        self.cycles += 1;
+        self.bus.tick();
+    }
 
-        self.execute(opcode);
+    fn fetch(&mut self) -> u8 {
+        if self.halt_bug {
+            self.halt_bug = false;
+            return self.read(self.registers.pc);
+        }
+
+        self.read_from_pc()
     }
 
-    fn read(&self, addr: u16) -> u8 {
-        self.bus.borrow().read(addr)
+    // Every memory access takes one M-cycle, which the rest of the machine sees before the access
+    fn read(&mut self, addr: u16) -> u8 {
+        self.cycle();
+        self.bus.read(addr)
     }
 
     fn write(&mut self, addr: u16, value: u8) {
-        self.bus.borrow_mut().write(addr, value)
+        self.cycle();
+        self.bus.write(addr, value);
+        self.invalidate_block_cache(addr);
+    }
+
+    // Reads without spending a cycle, for signals the CPU sees outside of bus accesses (IE / IF)
+    fn peek(&self, addr: u16) -> u8 {
+        self.bus.read(addr)
+    }
+
+    // Writes without spending a cycle, for registers the CPU updates as a side effect (IF / DIV)
+    fn poke(&mut self, addr: u16, value: u8) {
+        self.bus.write(addr, value);
+        self.invalidate_block_cache(addr);
     }
 
     // TODO: Verify we always need to increment PC after reading from it
@@ -59,14 +207,8 @@ impl CPU {
         value
     }
 
-    fn execute(&mut self, opcode: u8) {
-        let instruction = &INSTRUCTIONS[opcode as usize];
-
-        println!("Executing instruction: {} {}", instruction.name, opcode);
-
-        let cycles = (instruction.function)(self);
-
-        self.cycles += cycles;
+    fn execute(&mut self, opcode: u8) -> u32 {
+        (Self::HANDLERS[opcode as usize])(self)
     }
 
     fn set_zero_flag(&mut self, result: u8) {
@@ -84,11 +226,4 @@ impl CPU {
     fn set_half_carry_sub(&mut self, a: u8, b: u8) {
         self.registers.f.half_carry = a & 0xf < b & 0xf;
     }
-
-    fn no_impl(&self) {
-        println!(
-            "Attempted to invoke unimplemented opcode at PC={}",
-            self.registers.pc
-        );
-    }
 }
@@ -1,64 +1,286 @@
 // All instructions prefixed with "0xCB"
 
+use crate::bus::Bus;
 use crate::cpu::CPU;
 use paste::paste;
 
+/*
+    Expands the given macro once per operand of a prefixed instruction.
+    Any leading arguments (operation name, bit index, ...) are forwarded ahead of the register.
+*/
 macro_rules! implement_all_registers {
-    ($macro:ident) => {
-        $macro!(a);
-        $macro!(b);
-        $macro!(c);
-        $macro!(d);
-        $macro!(e);
-        $macro!(h);
-        $macro!(l);
-        $macro!(ind_hl);
+    ($macro:ident $(, $args:tt)*) => {
+        $macro!($($args,)* a);
+        $macro!($($args,)* b);
+        $macro!($($args,)* c);
+        $macro!($($args,)* d);
+        $macro!($($args,)* e);
+        $macro!($($args,)* h);
+        $macro!($($args,)* l);
+        $macro!($($args,)* ind_hl);
     };
 }
 
-macro_rules! rotate_left_carry {
-    (ind_hl) => {
+// Rotates and shifts: read the operand, transform it, write it back
+macro_rules! rotate_shift {
+    ($name:ident, $operation:ident, ind_hl) => {
         paste! {
-            pub(super) fn rotate_left_carry_ind_hl(&mut self) {
+            pub(super) fn [<$name _ind_hl>](&mut self) {
                 let addr = self.registers.hl();
 
                 let value = self.read(addr);
 
-                let most_significant_bit = (value & 0x8) >> 7;
+                let new_value = self.$operation(value);
 
-                let new_value = (value << 1) | most_significant_bit;
+                self.write(addr, new_value);
+            }
+        }
+    };
+    ($name:ident, $operation:ident, $reg:ident) => {
+        paste! {
+            pub(super) fn [<$name _ $reg>](&mut self) {
+                self.registers.$reg = self.$operation(self.registers.$reg);
+            }
+        }
+    };
+}
 
-                self.registers.f.carry = most_significant_bit == 1;
-                self.registers.f.half_carry = false;
-                self.registers.f.sub = false;
-                self.registers.f.zero = new_value == 1;
+macro_rules! test_bit {
+    ($bit:literal, ind_hl) => {
+        paste! {
+            pub(super) fn [<test_bit_ $bit _ind_hl>](&mut self) {
+                let value = self.read(self.registers.hl());
 
-                self.write(addr, new_value);
+                self.test_bit(value, $bit);
+            }
+        }
+    };
+    ($bit:literal, $reg:ident) => {
+        paste! {
+            pub(super) fn [<test_bit_ $bit _ $reg>](&mut self) {
+                self.test_bit(self.registers.$reg, $bit);
+            }
+        }
+    };
+}
+
+macro_rules! reset_bit {
+    ($bit:literal, ind_hl) => {
+        paste! {
+            pub(super) fn [<reset_bit_ $bit _ind_hl>](&mut self) {
+                let addr = self.registers.hl();
+
+                let value = self.read(addr);
+
+                self.write(addr, value & !(1 << $bit));
             }
         }
     };
-    ($reg:expr) => {
+    ($bit:literal, $reg:ident) => {
         paste! {
-            pub(super) fn [<rotate_left_carry_ $reg>](&mut self) {
-                let most_significant_bit = (self.registers.$reg & 0x8) >> 7;
+            pub(super) fn [<reset_bit_ $bit _ $reg>](&mut self) {
+                self.registers.$reg &= !(1 << $bit);
+            }
+        }
+    };
+}
 
-                self.registers.$reg = (self.registers.$reg << 1) | most_significant_bit;
+macro_rules! set_bit {
+    ($bit:literal, ind_hl) => {
+        paste! {
+            pub(super) fn [<set_bit_ $bit _ind_hl>](&mut self) {
+                let addr = self.registers.hl();
+
+                let value = self.read(addr);
 
-                self.registers.f.carry = most_significant_bit == 1;
-                self.registers.f.half_carry = false;
-                self.registers.f.sub = false;
-                self.registers.f.zero = self.registers.$reg == 1;
+                self.write(addr, value | (1 << $bit));
             }
         }
     };
+    ($bit:literal, $reg:ident) => {
+        paste! {
+            pub(super) fn [<set_bit_ $bit _ $reg>](&mut self) {
+                self.registers.$reg |= 1 << $bit;
+            }
+        }
+    };
+}
+
+impl<B: Bus> CPU<B> {
+    // Sets the flags shared by every rotate / shift / swap and hands back the result
+    fn rotate_shift_result(&mut self, result: u8, carry: bool) -> u8 {
+        self.registers.f.zero = result == 0;
+        self.registers.f.sub = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = carry;
+
+        result
+    }
+
+    fn rlc(&mut self, value: u8) -> u8 {
+        let most_significant_bit = value >> 7;
+
+        self.rotate_shift_result(
+            (value << 1) | most_significant_bit,
+            most_significant_bit == 1,
+        )
+    }
+
+    fn rrc(&mut self, value: u8) -> u8 {
+        let first_bit = value & 1;
+
+        self.rotate_shift_result((first_bit << 7) | (value >> 1), first_bit == 1)
+    }
+
+    fn rl(&mut self, value: u8) -> u8 {
+        let old_carry = self.registers.f.carry as u8;
+
+        self.rotate_shift_result((value << 1) | old_carry, value >> 7 == 1)
+    }
+
+    fn rr(&mut self, value: u8) -> u8 {
+        let old_carry = self.registers.f.carry as u8;
+
+        self.rotate_shift_result((old_carry << 7) | (value >> 1), value & 1 == 1)
+    }
+
+    fn sla(&mut self, value: u8) -> u8 {
+        self.rotate_shift_result(value << 1, value >> 7 == 1)
+    }
+
+    // Arithmetic right shift keeps the sign bit in place
+    fn sra(&mut self, value: u8) -> u8 {
+        self.rotate_shift_result((value & 0x80) | (value >> 1), value & 1 == 1)
+    }
+
+    fn swap(&mut self, value: u8) -> u8 {
+        self.rotate_shift_result(value.rotate_left(4), false)
+    }
+
+    fn srl(&mut self, value: u8) -> u8 {
+        self.rotate_shift_result(value >> 1, value & 1 == 1)
+    }
+
+    // BIT leaves the carry flag untouched
+    fn test_bit(&mut self, value: u8, bit: u8) {
+        self.registers.f.zero = value & (1 << bit) == 0;
+        self.registers.f.sub = false;
+        self.registers.f.half_carry = true;
+    }
+
+    implement_all_registers!(rotate_shift, rotate_left_carry, rlc);
+    implement_all_registers!(rotate_shift, rotate_right_carry, rrc);
+    implement_all_registers!(rotate_shift, rotate_left_through_carry, rl);
+    implement_all_registers!(rotate_shift, rotate_right_through_carry, rr);
+    implement_all_registers!(rotate_shift, shift_left_arithmetic, sla);
+    implement_all_registers!(rotate_shift, shift_right_arithmetic, sra);
+    implement_all_registers!(rotate_shift, swap, swap);
+    implement_all_registers!(rotate_shift, shift_right_logical, srl);
+
+    implement_all_registers!(test_bit, 0);
+    implement_all_registers!(test_bit, 1);
+    implement_all_registers!(test_bit, 2);
+    implement_all_registers!(test_bit, 3);
+    implement_all_registers!(test_bit, 4);
+    implement_all_registers!(test_bit, 5);
+    implement_all_registers!(test_bit, 6);
+    implement_all_registers!(test_bit, 7);
+
+    implement_all_registers!(reset_bit, 0);
+    implement_all_registers!(reset_bit, 1);
+    implement_all_registers!(reset_bit, 2);
+    implement_all_registers!(reset_bit, 3);
+    implement_all_registers!(reset_bit, 4);
+    implement_all_registers!(reset_bit, 5);
+    implement_all_registers!(reset_bit, 6);
+    implement_all_registers!(reset_bit, 7);
+
+    implement_all_registers!(set_bit, 0);
+    implement_all_registers!(set_bit, 1);
+    implement_all_registers!(set_bit, 2);
+    implement_all_registers!(set_bit, 3);
+    implement_all_registers!(set_bit, 4);
+    implement_all_registers!(set_bit, 5);
+    implement_all_registers!(set_bit, 6);
+    implement_all_registers!(set_bit, 7);
 }
 
-impl CPU {
-    pub(super) fn execute_prefixed_instr() -> u32 {
-        let num_cycles = 2;
+#[cfg(test)]
+mod tests {
+    use crate::cpu::test_helpers::make_cpu;
+
+    #[test]
+    fn test_rotate_left_carry() {
+        let mut cpu = make_cpu();
+        cpu.registers.b = 0x85;
+
+        cpu.rotate_left_carry_b();
+
+        assert_eq!(cpu.registers.b, 0x0B);
+        assert!(cpu.registers.f.carry);
+        assert!(!cpu.registers.f.zero);
+    }
+
+    #[test]
+    fn test_rotate_right_through_carry() {
+        let mut cpu = make_cpu();
+        cpu.registers.a = 0x01;
+        cpu.registers.f.carry = false;
+
+        cpu.rotate_right_through_carry_a();
+
+        assert_eq!(cpu.registers.a, 0x00);
+        assert!(cpu.registers.f.carry);
+        assert!(cpu.registers.f.zero);
+    }
+
+    #[test]
+    fn test_shift_right_arithmetic_keeps_sign() {
+        let mut cpu = make_cpu();
+        cpu.registers.d = 0x8A;
+
+        cpu.shift_right_arithmetic_d();
 
-        num_cycles
+        assert_eq!(cpu.registers.d, 0xC5);
+        assert!(!cpu.registers.f.carry);
     }
 
-    implement_all_registers!(rotate_left_carry);
+    #[test]
+    fn test_swap_ind_hl() {
+        let mut cpu = make_cpu();
+        cpu.registers.set_hl(0xC000);
+        cpu.write(0xC000, 0xF1);
+
+        cpu.swap_ind_hl();
+
+        assert_eq!(cpu.read(0xC000), 0x1F);
+        assert!(!cpu.registers.f.carry);
+    }
+
+    #[test]
+    fn test_bit_leaves_carry() {
+        let mut cpu = make_cpu();
+        cpu.registers.h = 0x80;
+        cpu.registers.f.carry = true;
+
+        cpu.test_bit_7_h();
+        assert!(!cpu.registers.f.zero);
+
+        cpu.test_bit_6_h();
+        assert!(cpu.registers.f.zero);
+        assert!(cpu.registers.f.half_carry);
+        assert!(cpu.registers.f.carry);
+    }
+
+    #[test]
+    fn test_reset_and_set_bit() {
+        let mut cpu = make_cpu();
+        cpu.registers.e = 0xFF;
+
+        cpu.reset_bit_3_e();
+        assert_eq!(cpu.registers.e, 0xF7);
+
+        cpu.set_bit_3_e();
+        assert_eq!(cpu.registers.e, 0xFF);
+    }
 }
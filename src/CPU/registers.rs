@@ -1,7 +1,7 @@
 use super::utils::*;
 use paste::paste;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct FlagRegister {
     pub zero: bool,
     pub sub: bool,
@@ -27,9 +27,7 @@ impl std::convert::From<FlagRegister> for u8 {
         let c = if value.half_carry { 1 } else { 0 };
         let d = if value.carry { 1 } else { 0 };
 
-        let register_value = ((a << 7) | (b << 6) | (c << 5) | (d << 4)) as u8;
-
-        register_value
+        ((a << 7) | (b << 6) | (c << 5) | (d << 4)) as u8
     }
 }
 
@@ -100,14 +98,14 @@ impl Registers {
     reg_pairs!(h, l);
 
     pub(super) fn af(&self) -> u16 {
-        build_16_bit(self.a, u8::from(self.f.clone()))
+        build_16_bit(self.a, u8::from(self.f))
     }
 
     pub(super) fn set_af(&mut self, value: u16) {
         let low_byte = get_low_byte(value);
         let high_byte = get_high_byte(value);
 
-        self.a = low_byte;
-        self.f = FlagRegister::from(high_byte);
+        self.a = high_byte;
+        self.f = FlagRegister::from(low_byte);
     }
 }
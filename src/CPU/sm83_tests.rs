@@ -0,0 +1,206 @@
+use std::{
+    env, fs,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+use serde_json::Value;
+
+use super::registers::FlagRegister;
+use super::test_helpers::{BusCycle, FakeBus, make_cpu};
+use super::{CPU, RunState};
+use crate::bus::{Bus, IE_ADDR};
+
+/*
+    Runner for the community single step tests (github.com/SingleStepTests/sm83),
+    which have one JSON file of cases per opcode: "00.json" through "cb ff.json".
+    Point SM83_TESTS_DIR at the directory holding them, or place them in tests/sm83/v1.
+    Without either the test passes without running anything.
+
+    The vectors come from a CPU that overlaps each opcode fetch with the end of the previous
+    instruction, so PC starts one past the opcode and the last cycle listed fetches the next one.
+    We fetch at the start of each instruction instead, so PC is rewound by one
+    and our cycle log is compared against theirs shifted by one cycle.
+*/
+
+fn vectors_dir() -> Option<PathBuf> {
+    let dir = match env::var_os("SM83_TESTS_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sm83/v1"),
+    };
+
+    dir.is_dir().then_some(dir)
+}
+
+fn byte(state: &Value, key: &str) -> u8 {
+    state[key].as_u64().unwrap() as u8
+}
+
+fn word(state: &Value, key: &str) -> u16 {
+    state[key].as_u64().unwrap() as u16
+}
+
+fn ram_entries(state: &Value) -> impl Iterator<Item = (u16, u8)> {
+    state["ram"].as_array().unwrap().iter().map(|entry| {
+        (
+            entry[0].as_u64().unwrap() as u16,
+            entry[1].as_u64().unwrap() as u8,
+        )
+    })
+}
+
+fn load_state(cpu: &mut CPU<FakeBus>, state: &Value) {
+    cpu.registers.a = byte(state, "a");
+    cpu.registers.f = FlagRegister::from(byte(state, "f"));
+    cpu.registers.b = byte(state, "b");
+    cpu.registers.c = byte(state, "c");
+    cpu.registers.d = byte(state, "d");
+    cpu.registers.e = byte(state, "e");
+    cpu.registers.h = byte(state, "h");
+    cpu.registers.l = byte(state, "l");
+    cpu.registers.sp = word(state, "sp");
+    cpu.registers.pc = word(state, "pc").wrapping_sub(1);
+    cpu.ime = byte(state, "ime") != 0;
+
+    if state.get("ie").is_some() {
+        cpu.bus.write(IE_ADDR, byte(state, "ie"));
+    }
+
+    for (addr, value) in ram_entries(state) {
+        cpu.bus.write(addr, value);
+    }
+}
+
+fn check_state(cpu: &CPU<FakeBus>, state: &Value) -> Result<(), String> {
+    let registers = [
+        ("a", cpu.registers.a as u16, word(state, "a")),
+        ("f", u8::from(cpu.registers.f) as u16, word(state, "f")),
+        ("b", cpu.registers.b as u16, word(state, "b")),
+        ("c", cpu.registers.c as u16, word(state, "c")),
+        ("d", cpu.registers.d as u16, word(state, "d")),
+        ("e", cpu.registers.e as u16, word(state, "e")),
+        ("h", cpu.registers.h as u16, word(state, "h")),
+        ("l", cpu.registers.l as u16, word(state, "l")),
+        ("sp", cpu.registers.sp, word(state, "sp")),
+        ("pc", cpu.registers.pc, word(state, "pc").wrapping_sub(1)),
+    ];
+
+    for (name, actual, expected) in registers {
+        if actual != expected {
+            return Err(format!("{name} is ${actual:04X}, expected ${expected:04X}"));
+        }
+    }
+
+    // EI only takes effect after the next instruction, which the vectors flag separately
+    let expected_ime = byte(state, "ime") != 0 || state.get("ei").is_some_and(|ei| ei != 0);
+    let ime = cpu.ime || cpu.run_state == RunState::EnablingInterrupts;
+    if ime != expected_ime {
+        return Err(format!("ime is {ime}, expected {expected_ime}"));
+    }
+
+    for (addr, expected) in ram_entries(state) {
+        let actual = cpu.peek(addr);
+
+        if actual != expected {
+            return Err(format!(
+                "${addr:04X} is ${actual:02X}, expected ${expected:02X}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn expected_cycles(case: &Value) -> Vec<BusCycle> {
+    let cycle = |entry: &Value| {
+        let kind = entry[2].as_str().unwrap_or("---");
+        let addr = entry[0].as_u64().unwrap_or(0) as u16;
+        let value = entry[1].as_u64().unwrap_or(0) as u8;
+
+        if kind.contains('r') {
+            BusCycle::Read(addr, value)
+        } else if kind.contains('w') {
+            BusCycle::Write(addr, value)
+        } else {
+            BusCycle::Idle
+        }
+    };
+
+    case["cycles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(cycle)
+        .collect()
+}
+
+fn run_case(case: &Value) -> Result<(), String> {
+    let mut cpu = make_cpu();
+    load_state(&mut cpu, &case["initial"]);
+    cpu.bus.cycles.get_mut().clear();
+
+    // Anything the CPU panics on (e.g. arithmetic overflow) just fails this case
+    panic::catch_unwind(AssertUnwindSafe(|| cpu.run_instruction()))
+        .map_err(|_| "panicked".to_string())?;
+
+    check_state(&cpu, &case["final"])?;
+
+    let actual = cpu.bus.cycles.borrow().clone();
+    let expected = expected_cycles(case);
+
+    // Every case takes at least one cycle, so an empty list is a malformed vector
+    if expected.is_empty()
+        || actual.len() != expected.len()
+        || actual[1..] != expected[..expected.len() - 1]
+    {
+        return Err(format!("bus cycles were {actual:?}, expected {expected:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_sm83_vectors() {
+    let Some(dir) = vectors_dir() else {
+        println!("SM83 test vectors not found, skipping");
+        return;
+    };
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut failing_opcodes = Vec::new();
+
+    for path in files {
+        let opcode = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let cases: Vec<Value> = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        let mut passed = 0;
+        let mut first_failure = None;
+
+        for case in &cases {
+            match run_case(case) {
+                Ok(()) => passed += 1,
+                Err(why) => {
+                    first_failure.get_or_insert_with(|| format!("{}: {why}", case["name"]));
+                }
+            }
+        }
+
+        println!("{opcode}: {passed}/{} passed", cases.len());
+
+        if let Some(failure) = first_failure {
+            println!("    first failure {failure}");
+            failing_opcodes.push(opcode);
+        }
+    }
+
+    assert!(
+        failing_opcodes.is_empty(),
+        "Failing opcodes: {failing_opcodes:?}"
+    );
+}
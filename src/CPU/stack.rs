@@ -1,7 +1,8 @@
 use super::CPU;
 use super::utils::*;
+use crate::bus::Bus;
 
-impl CPU {
+impl<B: Bus> CPU<B> {
     fn read_from_sp(&mut self) -> u8 {
         let addr = self.registers.sp;
         let value = self.read(addr);
@@ -11,9 +12,11 @@ impl CPU {
     }
 
     // Push instructions
+    // Every push (PUSH, CALL, RST) spends an internal cycle decrementing SP before writing
     fn push(&mut self, value: u16) {
+        self.cycle();
+
         wrapping_sub(&mut self.registers.sp, 1);
-        self.registers.sp = self.registers.sp.wrapping_sub(1);
         let high_byte = get_high_byte(value);
         self.write(self.registers.sp, high_byte);
 
@@ -70,23 +73,29 @@ impl CPU {
     }
 
     // Call instructions
+    fn call_address(&mut self, addr: u16) {
+        self.push(self.registers.pc);
+
+        self.jump_to_address(addr);
+    }
+
     pub(super) fn call(&mut self) {
-        self.jump_16_bit();
+        let addr = self.read_imm_16_bit();
 
-        self.push(self.registers.pc);
+        self.call_address(addr);
     }
 
+    // The target address is always read, even when the call isn't taken
     fn conditional_call(&mut self, condition: bool) -> u32 {
-        let mut num_cycles = 3;
-        if condition {
-            wrapping_add(&mut self.registers.pc, 2);
-            num_cycles
-        } else {
-            self.call();
+        let addr = self.read_imm_16_bit();
 
-            num_cycles = 6;
-            num_cycles
+        if !condition {
+            return 3;
         }
+
+        self.call_address(addr);
+
+        6
     }
 
     pub(super) fn call_nz(&mut self) -> u32 {
@@ -119,6 +128,9 @@ impl CPU {
     }
 
     pub(super) fn return_with_condition(&mut self, condition: bool) -> u32 {
+        // Evaluating the condition costs an extra internal cycle
+        self.cycle();
+
         let mut num_cycles: u32 = 2;
         if !condition {
             num_cycles
@@ -147,9 +159,10 @@ impl CPU {
         self.return_with_condition(self.registers.f.carry)
     }
 
+    // Unlike EI, RETI enables interrupts without any delay
     pub(super) fn ret_enable_interrupts(&mut self) {
         self.instr_return();
-        self.enable_interrupts();
+        self.ime = true;
     }
 
     // Reset instructions
@@ -191,3 +204,94 @@ impl CPU {
         self.reset(0x38)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::Bus;
+    use crate::cpu::test_helpers::make_cpu;
+
+    #[test]
+    fn test_push_writes_after_internal_cycle() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.registers.sp = 0xFFFE;
+        cpu.registers.set_bc(0x1234);
+        cpu.bus.write(0xC000, 0xC5); // PUSH BC
+        cpu.bus.writes.clear();
+
+        cpu.tick();
+
+        assert_eq!(cpu.cycles, 4);
+        assert_eq!(cpu.bus.ticks, 4);
+        assert_eq!(cpu.bus.writes, vec![(3, 0xFFFD), (4, 0xFFFC)]);
+    }
+
+    #[test]
+    fn test_push_then_pop_round_trips() {
+        let mut cpu = make_cpu();
+        cpu.registers.sp = 0xFFFE;
+        cpu.registers.set_de(0xBEEF);
+
+        cpu.push_de();
+
+        assert_eq!(cpu.registers.sp, 0xFFFC);
+        assert_eq!(cpu.peek(0xFFFD), 0xBE);
+        assert_eq!(cpu.peek(0xFFFC), 0xEF);
+
+        cpu.pop_bc();
+
+        assert_eq!(cpu.registers.bc(), 0xBEEF);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+    }
+
+    #[test]
+    fn test_stack_pointer_wraps() {
+        let mut cpu = make_cpu();
+        cpu.registers.sp = 0x0001;
+        cpu.registers.set_hl(0x1234);
+
+        cpu.push_hl();
+
+        assert_eq!(cpu.registers.sp, 0xFFFF);
+        assert_eq!(cpu.peek(0x0000), 0x12);
+        assert_eq!(cpu.peek(0xFFFF), 0x34);
+
+        cpu.pop_de();
+
+        assert_eq!(cpu.registers.de(), 0x1234);
+        assert_eq!(cpu.registers.sp, 0x0001);
+    }
+
+    #[test]
+    fn test_conditional_call_not_taken() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.registers.f.zero = true;
+        cpu.bus.write(0xC000, 0xC4); // CALL NZ, $1234
+        cpu.bus.write(0xC001, 0x34);
+        cpu.bus.write(0xC002, 0x12);
+
+        cpu.tick();
+
+        assert_eq!(cpu.registers.pc, 0xC003);
+        assert_eq!(cpu.cycles, 3);
+    }
+
+    #[test]
+    fn test_conditional_call_taken() {
+        let mut cpu = make_cpu();
+        cpu.registers.pc = 0xC000;
+        cpu.registers.sp = 0xFFFE;
+        cpu.bus.write(0xC000, 0xC4); // CALL NZ, $1234
+        cpu.bus.write(0xC001, 0x34);
+        cpu.bus.write(0xC002, 0x12);
+
+        cpu.tick();
+
+        assert_eq!(cpu.registers.pc, 0x1234);
+        assert_eq!(cpu.peek(0xFFFD), 0xC0);
+        assert_eq!(cpu.peek(0xFFFC), 0x03);
+        assert_eq!(cpu.cycles, 6);
+        assert_eq!(cpu.bus.ticks, 6);
+    }
+}
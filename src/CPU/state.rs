@@ -0,0 +1,171 @@
+use super::registers::FlagRegister;
+use super::{CPU, RunState};
+use crate::bus::Bus;
+
+// Copy of the CPU's registers and status, for debuggers and tests to inspect without borrowing the CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuState {
+    pub a: u8,
+    pub f: FlagRegister,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub ime: bool,
+    pub run_state: RunState,
+    pub cycles: u64, // Total M-cycles elapsed
+}
+
+impl CpuState {
+    pub fn af(&self) -> u16 {
+        u16::from_be_bytes([self.a, u8::from(self.f)])
+    }
+
+    pub fn bc(&self) -> u16 {
+        u16::from_be_bytes([self.b, self.c])
+    }
+
+    pub fn de(&self) -> u16 {
+        u16::from_be_bytes([self.d, self.e])
+    }
+
+    pub fn hl(&self) -> u16 {
+        u16::from_be_bytes([self.h, self.l])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+    PC,
+}
+
+impl<B: Bus> CPU<B> {
+    pub fn state(&self) -> CpuState {
+        let registers = &self.registers;
+
+        CpuState {
+            a: registers.a,
+            f: registers.f,
+            b: registers.b,
+            c: registers.c,
+            d: registers.d,
+            e: registers.e,
+            h: registers.h,
+            l: registers.l,
+            sp: registers.sp,
+            pc: registers.pc,
+            ime: self.ime,
+            run_state: self.run_state,
+            cycles: self.cycles,
+        }
+    }
+
+    pub fn register(&self, register: Register) -> u16 {
+        let registers = &self.registers;
+
+        match register {
+            Register::A => registers.a as u16,
+            Register::F => u8::from(registers.f) as u16,
+            Register::B => registers.b as u16,
+            Register::C => registers.c as u16,
+            Register::D => registers.d as u16,
+            Register::E => registers.e as u16,
+            Register::H => registers.h as u16,
+            Register::L => registers.l as u16,
+            Register::AF => registers.af(),
+            Register::BC => registers.bc(),
+            Register::DE => registers.de(),
+            Register::HL => registers.hl(),
+            Register::SP => registers.sp,
+            Register::PC => registers.pc,
+        }
+    }
+
+    /*
+        8-bit registers take the low byte of the value. The low nibble of F doesn't exist on hardware,
+        so it always reads back as 0.
+        Setting PC between instructions is safe, the block cache checks PC before each instruction.
+    */
+    pub fn set_register(&mut self, register: Register, value: u16) {
+        let registers = &mut self.registers;
+        let byte = value as u8;
+
+        match register {
+            Register::A => registers.a = byte,
+            Register::F => registers.f = FlagRegister::from(byte),
+            Register::B => registers.b = byte,
+            Register::C => registers.c = byte,
+            Register::D => registers.d = byte,
+            Register::E => registers.e = byte,
+            Register::H => registers.h = byte,
+            Register::L => registers.l = byte,
+            Register::AF => registers.set_af(value),
+            Register::BC => registers.set_bc(value),
+            Register::DE => registers.set_de(value),
+            Register::HL => registers.set_hl(value),
+            Register::SP => registers.sp = value,
+            Register::PC => registers.pc = value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::test_helpers::make_cpu;
+
+    #[test]
+    fn test_set_register_round_trips() {
+        let mut cpu = make_cpu();
+
+        cpu.set_register(Register::AF, 0x12FF);
+        cpu.set_register(Register::DE, 0x3456);
+        cpu.set_register(Register::L, 0x1278);
+        cpu.set_register(Register::PC, 0xC000);
+
+        let state = cpu.state();
+
+        assert_eq!(state.a, 0x12);
+        assert_eq!(state.af(), 0x12F0);
+        assert!(state.f.zero && state.f.carry);
+        assert_eq!(state.de(), 0x3456);
+        assert_eq!(state.l, 0x78);
+        assert_eq!(cpu.register(Register::PC), 0xC000);
+    }
+
+    #[test]
+    fn test_state_tracks_execution() {
+        let mut cpu = make_cpu();
+        cpu.set_register(Register::PC, 0xC000);
+        cpu.write(0xC000, 0xFB); // EI
+        cpu.write(0xC001, 0x76); // HALT
+
+        cpu.tick();
+        assert_eq!(cpu.state().run_state, RunState::EnablingInterrupts);
+
+        cpu.tick();
+
+        let state = cpu.state();
+        assert!(state.ime);
+        assert_eq!(state.run_state, RunState::Halted);
+        assert_eq!(state.pc, 0xC002);
+        assert_eq!(state.cycles, cpu.cycles);
+    }
+}
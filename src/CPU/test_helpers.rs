@@ -1,50 +1,75 @@
-use std::{cell::RefCell, rc::Rc};
+use std::cell::RefCell;
 
 use crate::{
-    bus::{Bus, SystemBus},
-    cpu::{CPU, registers::Registers},
+    bus::{Bus, IF_ADDR, Interrupt},
+    cpu::CPU,
 };
 
-struct FakeBus {
+// What the CPU did with the bus during one M-cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BusCycle {
+    Idle,
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+pub(super) struct FakeBus {
     memory: Vec<u8>,
-    interrupts_enabled: bool,
+    pub ticks: u64,
+    pub writes: Vec<(u64, u16)>, // Bus tick each write landed on, and its address
+    /*
+        One entry per tick, filled in by the first access made during that cycle.
+        The CPU also peeks at IE / IF without spending a cycle, which only shows up
+        here if it happens during an otherwise idle cycle.
+    */
+    pub cycles: RefCell<Vec<BusCycle>>,
 }
 
 impl FakeBus {
     pub fn new() -> Self {
         Self {
-            memory: vec![0; (std::u16::MAX as usize) + 1],
-            interrupts_enabled: false,
+            memory: vec![0; (u16::MAX as usize) + 1],
+            ticks: 0,
+            writes: Vec::new(),
+            cycles: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn log_access(&self, access: BusCycle) {
+        if let Some(cycle @ BusCycle::Idle) = self.cycles.borrow_mut().last_mut() {
+            *cycle = access;
         }
     }
 }
 
 impl Bus for FakeBus {
     fn read(&self, addr: u16) -> u8 {
-        self.memory[addr as usize]
+        let value = self.memory[addr as usize];
+        self.log_access(BusCycle::Read(addr, value));
+        value
     }
 
     fn write(&mut self, addr: u16, data: u8) {
         self.memory[addr as usize] = data;
+        self.writes.push((self.ticks, addr));
+        self.log_access(BusCycle::Write(addr, data));
     }
 
-    fn request_interrupt(&mut self) {}
-
-    fn enable_interrupts(&mut self) {
-        self.interrupts_enabled = true;
+    fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.memory[IF_ADDR as usize] |= interrupt.mask();
     }
 
-    fn disable_interrupts(&mut self) {
-        self.interrupts_enabled = false;
+    fn tick(&mut self) {
+        self.ticks += 1;
+        self.cycles.get_mut().push(BusCycle::Idle);
     }
 
-    fn interrupts_enabled(&self) -> bool {
-        self.interrupts_enabled
+    fn speed_switch(&mut self) -> bool {
+        false
     }
 }
 
-pub(super) fn make_cpu() -> CPU {
-    let new_cpu = CPU::new(Rc::new(RefCell::new(FakeBus::new())));
-
-    new_cpu
+// The CPU owns its bus, so tests reach the FakeBus through `cpu.bus`
+pub(super) fn make_cpu() -> CPU<FakeBus> {
+    CPU::new(FakeBus::new())
 }
@@ -1,9 +1,9 @@
 pub(super) fn wrapping_add(value: &mut u16, amount: u16) {
-    value.wrapping_add(amount);
+    *value = value.wrapping_add(amount);
 }
 
 pub(super) fn wrapping_sub(value: &mut u16, amount: u16) {
-    value.wrapping_sub(amount);
+    *value = value.wrapping_sub(amount);
 }
 
 pub(super) fn carry_occurred_8(a: u8, b: u8) -> bool {
@@ -30,10 +30,13 @@ pub(super) fn half_carry_occurred_8_sub(a: u8, b: u8) -> bool {
     a & 0xf < b & 0xf
 }
 
+// No 16-bit subtraction sets flags yet, so only the tests use these two
+#[cfg(test)]
 pub(super) fn carry_occurred_16_sub(a: u16, b: u16) -> bool {
     a < b
 }
 
+#[cfg(test)]
 pub(super) fn half_carry_occurred_16_sub(a: u16, b: u16) -> bool {
     a & 0xfff < b & 0xfff
 }
@@ -62,6 +65,26 @@ pub(super) fn build_16_bit(high: u8, low: u8) -> u16 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_wrapping_add() {
+        let mut value = 0xFFFF;
+        wrapping_add(&mut value, 1);
+        assert_eq!(value, 0x0000);
+
+        wrapping_add(&mut value, 0x1234);
+        assert_eq!(value, 0x1234);
+    }
+
+    #[test]
+    fn test_wrapping_sub() {
+        let mut value = 0x0000;
+        wrapping_sub(&mut value, 1);
+        assert_eq!(value, 0xFFFF);
+
+        wrapping_sub(&mut value, 0x0FFF);
+        assert_eq!(value, 0xF000);
+    }
+
     #[test]
     fn test_get_high_byte() {
         assert_eq!(get_high_byte(0xABCD), 0xAB);
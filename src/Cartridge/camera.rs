@@ -0,0 +1,358 @@
+use super::mapper::{Mapper, Ram, Rom};
+
+pub const CAMERA_WIDTH: usize = 128;
+pub const CAMERA_HEIGHT: usize = 112;
+
+// Brightness of each pixel, 0 black to 255 white, row by row
+pub type CameraFrame = [[u8; CAMERA_WIDTH]; CAMERA_HEIGHT];
+
+const REGISTER_COUNT: usize = 0x36;
+const DITHER_MATRIX: usize = 0x06; // 4x4 matrix of 3 thresholds each, up to 0x35
+const IMAGE_ADDR: u16 = 0xA100; // Captures land in RAM bank 0 as 16x14 tiles
+
+// Edge enhancement strength, selected by bits 4-6 of register 4
+const EDGE_RATIOS: [f32; 8] = [0.5, 0.75, 1.0, 1.25, 2.0, 3.0, 4.0, 5.0];
+
+// Where captured pictures come from, e.g. a webcam, a static image or a test pattern
+pub trait ImageSource {
+    fn capture(&mut self, frame: &mut CameraFrame);
+}
+
+// Shows the same picture every time
+pub struct StaticImage {
+    frame: Box<CameraFrame>,
+}
+
+impl StaticImage {
+    pub fn new(frame: CameraFrame) -> Self {
+        Self {
+            frame: Box::new(frame),
+        }
+    }
+}
+
+impl ImageSource for StaticImage {
+    fn capture(&mut self, frame: &mut CameraFrame) {
+        *frame = *self.frame;
+    }
+}
+
+// A left to right gradient from black to white
+pub struct TestPattern;
+
+impl ImageSource for TestPattern {
+    fn capture(&mut self, frame: &mut CameraFrame) {
+        for row in frame.iter_mut() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = (x * 255 / (CAMERA_WIDTH - 1)) as u8;
+            }
+        }
+    }
+}
+
+/*
+    The Game Boy Camera's mapper banks 128 KiB of RAM like MBC3, and maps the registers of its
+    M64282FP sensor over 0xA000 -> 0xBFFF when bit 4 of the RAM bank is set. Setting bit 0 of
+    register 0 takes a picture, which is processed into 2 bit tiles in RAM once the exposure is done.
+    RAM can always be read, but only written while enabled.
+*/
+pub struct Camera {
+    rom: Rom,
+    ram: Ram,
+    source: Box<dyn ImageSource>,
+    registers: [u8; REGISTER_COUNT],
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    capture_cycles: Option<u32>, // 4 MiHz cycles left until the picture is ready
+}
+
+impl Camera {
+    pub fn new(rom: Rom, ram: Ram) -> Self {
+        Self {
+            rom,
+            ram,
+            source: Box::new(TestPattern),
+            registers: [0; REGISTER_COUNT],
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            capture_cycles: None,
+        }
+    }
+
+    fn registers_mapped(&self) -> bool {
+        self.ram_bank & 0x10 != 0
+    }
+
+    fn exposure(&self) -> u16 {
+        u16::from_be_bytes([self.registers[2], self.registers[3]])
+    }
+
+    fn write_register(&mut self, addr: u16, data: u8) {
+        let register = (addr & 0x7F) as usize;
+
+        match register {
+            // Clearing the start bit can't cancel a capture in progress
+            0 => {
+                let busy = self.capture_cycles.is_some();
+                self.registers[0] = (data & 0x06) | busy as u8;
+
+                if data & 0x01 != 0 && !busy {
+                    self.start_capture();
+                }
+            }
+            1..REGISTER_COUNT => self.registers[register] = data,
+            _ => {}
+        }
+    }
+
+    // Sensor readout takes a fixed time plus the exposure, in units of 16 M-cycles
+    fn start_capture(&mut self) {
+        let n_bit = self.registers[1] & 0x80 != 0;
+        let m_cycles = 32446 + if n_bit { 0 } else { 512 } + 16 * self.exposure() as u32;
+
+        self.registers[0] |= 0x01;
+        self.capture_cycles = Some(m_cycles * 4);
+    }
+
+    fn finish_capture(&mut self) {
+        let mut frame = [[0; CAMERA_WIDTH]; CAMERA_HEIGHT];
+        self.source.capture(&mut frame);
+
+        let levels = self.sense(&frame);
+
+        for (y, row) in levels.iter().enumerate() {
+            for (x, &level) in row.iter().enumerate() {
+                let color = self.dither(x, y, level);
+
+                let tile = (y / 8) * (CAMERA_WIDTH / 8) + x / 8;
+                let addr = IMAGE_ADDR + (tile * 16 + (y % 8) * 2) as u16;
+                let bit = 0x80 >> (x % 8);
+
+                for (plane, addr) in [addr, addr + 1].into_iter().enumerate() {
+                    let byte = self.ram.read(0, addr) & !bit;
+                    let set = if color & (1 << plane) != 0 { bit } else { 0 };
+                    self.ram.write(0, addr, byte | set);
+                }
+            }
+        }
+
+        self.registers[0] &= !0x01;
+        self.capture_cycles = None;
+    }
+
+    /*
+        What the sensor outputs for each pixel: the light it saw scaled by the exposure time
+        (0x1000 passes the image through unchanged) and the gain, 1.5 dB per step of register 1.
+        With N set and both VH bits, each pixel is sharpened against its 4 neighbours.
+    */
+    fn sense(&self, frame: &CameraFrame) -> Vec<[f32; CAMERA_WIDTH]> {
+        let gain = 10f32.powf((self.registers[1] & 0x1F) as f32 * 1.5 / 20.0);
+        let scale = gain * self.exposure() as f32 / 0x1000 as f32;
+
+        let exposed: Vec<[f32; CAMERA_WIDTH]> = frame
+            .iter()
+            .map(|row| row.map(|pixel| pixel as f32 * scale))
+            .collect();
+
+        if self.registers[1] & 0xE0 != 0xE0 {
+            return exposed;
+        }
+
+        let ratio = EDGE_RATIOS[((self.registers[4] >> 4) & 0x07) as usize];
+        let at = |x: usize, y: usize| exposed[y.min(CAMERA_HEIGHT - 1)][x.min(CAMERA_WIDTH - 1)];
+
+        (0..CAMERA_HEIGHT)
+            .map(|y| {
+                std::array::from_fn(|x| {
+                    let neighbours = at(x.saturating_sub(1), y)
+                        + at(x + 1, y)
+                        + at(x, y.saturating_sub(1))
+                        + at(x, y + 1);
+
+                    at(x, y) + ratio * (4.0 * at(x, y) - neighbours)
+                })
+            })
+            .collect()
+    }
+
+    // Compares the pixel against its 3 thresholds in the dither matrix, darker means a higher color
+    fn dither(&self, x: usize, y: usize, level: f32) -> u8 {
+        let entry = DITHER_MATRIX + ((y % 4) * 4 + x % 4) * 3;
+        let thresholds = &self.registers[entry..entry + 3];
+
+        3 - thresholds
+            .iter()
+            .take_while(|&&threshold| level >= threshold as f32)
+            .count() as u8
+    }
+}
+
+impl Mapper for Camera {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    // Bank 0 can be mapped into the switchable window
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = data & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = data & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = data & 0x1F,
+            _ => {}
+        }
+    }
+
+    // Only register 0 can be read back, the rest read as 0
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.registers_mapped() {
+            return self.ram.read(self.ram_bank as usize, addr);
+        }
+
+        match addr & 0x7F {
+            0 => self.registers[0] & 0x07,
+            _ => 0x00,
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if self.registers_mapped() {
+            self.write_register(addr, data);
+        } else if self.ram_enabled {
+            self.ram.write(self.ram_bank as usize, addr, data);
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ => self.ram_bank as u16,
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.ram.data().to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.ram.load(data);
+    }
+
+    fn tick(&mut self, t_cycles: u8) {
+        let Some(cycles) = self.capture_cycles else {
+            return;
+        };
+
+        match cycles.checked_sub(t_cycles as u32) {
+            Some(cycles) if cycles > 0 => self.capture_cycles = Some(cycles),
+            _ => self.finish_capture(),
+        }
+    }
+
+    fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.source = source;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    // Registers mapped, exposure 0x1000, no gain and thresholds 0x40 / 0x80 / 0xC0 everywhere
+    fn make_camera(source: impl ImageSource + 'static) -> Camera {
+        let mut camera = Camera::new(
+            Rom::new(vec![0; 4 * ROM_BANK_SIZE]),
+            Ram::new(16 * RAM_BANK_SIZE),
+        );
+        camera.set_image_source(Box::new(source));
+
+        camera.write_rom(0x4000, 0x10);
+        camera.write_ram(0xA002, 0x10);
+        camera.write_ram(0xA003, 0x00);
+        for entry in 0..16 {
+            for (i, threshold) in [0x40, 0x80, 0xC0].into_iter().enumerate() {
+                camera.write_ram(0xA006 + entry * 3 + i as u16, threshold);
+            }
+        }
+
+        camera
+    }
+
+    fn capture(camera: &mut Camera) {
+        camera.write_ram(0xA000, 0x01);
+        while camera.read_ram(0xA000) & 0x01 != 0 {
+            camera.tick(4);
+        }
+        camera.write_rom(0x4000, 0x00);
+    }
+
+    #[test]
+    fn test_capture_dithers_into_tiles() {
+        // Top half mid grey, bottom half white
+        let mut frame = [[0xFF; CAMERA_WIDTH]; CAMERA_HEIGHT];
+        for row in frame.iter_mut().take(CAMERA_HEIGHT / 2) {
+            *row = [0x90; CAMERA_WIDTH];
+        }
+
+        let mut camera = make_camera(StaticImage::new(frame));
+        capture(&mut camera);
+
+        // Color 1 in the first tile, color 0 in the last
+        assert_eq!(camera.read_ram(0xA100), 0xFF);
+        assert_eq!(camera.read_ram(0xA101), 0x00);
+        assert_eq!(camera.read_ram(0xAEFE), 0x00);
+        assert_eq!(camera.read_ram(0xAEFF), 0x00);
+    }
+
+    #[test]
+    fn test_exposure_and_busy_time() {
+        let mut camera = make_camera(TestPattern);
+
+        // Half the exposure turns the right edge of the gradient from white to color 2
+        camera.write_ram(0xA002, 0x08);
+        camera.write_ram(0xA000, 0x01);
+        assert_eq!(camera.read_ram(0xA000) & 0x01, 0x01);
+
+        let mut m_cycles = 0;
+        while camera.read_ram(0xA000) & 0x01 != 0 {
+            camera.tick(4);
+            m_cycles += 1;
+        }
+        assert_eq!(m_cycles, 32446 + 512 + 16 * 0x0800);
+
+        camera.write_rom(0x4000, 0x00);
+        let last_tile_in_row = 0xA100 + 15 * 16;
+        assert_eq!(camera.read_ram(last_tile_in_row) & 0x01, 0x00);
+        assert_eq!(camera.read_ram(last_tile_in_row + 1) & 0x01, 0x01);
+        // Left edge stays black
+        assert_eq!(camera.read_ram(0xA100) & 0x80, 0x80);
+        assert_eq!(camera.read_ram(0xA101) & 0x80, 0x80);
+    }
+
+    #[test]
+    fn test_registers_are_write_only() {
+        let mut camera = make_camera(TestPattern);
+
+        camera.write_ram(0xA001, 0xE5);
+        assert_eq!(camera.read_ram(0xA001), 0x00);
+
+        camera.write_ram(0xA080, 0x04); // Register 0, mirrored every 0x80 bytes
+        assert_eq!(camera.read_ram(0xA000), 0x04);
+
+        // RAM reads don't need enabling, writes do
+        camera.write_rom(0x4000, 0x01);
+        camera.write_ram(0xA000, 0x12);
+        assert_eq!(camera.read_ram(0xA000), 0x00);
+
+        camera.write_rom(0x0000, 0x0A);
+        camera.write_ram(0xA000, 0x12);
+        camera.write_rom(0x0000, 0x00);
+        assert_eq!(camera.read_ram(0xA000), 0x12);
+    }
+}
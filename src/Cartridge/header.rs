@@ -1,9 +1,374 @@
-struct CartridgeHeader {
-    data: Vec<u8>,
+use std::{error::Error, fmt};
+
+use super::mapper::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+// Logo the boot ROM compares against before handing over to the cartridge
+pub(crate) const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+pub(crate) const LOGO_ADDR: usize = 0x104;
+const TITLE_ADDR: usize = 0x134;
+const MANUFACTURER_ADDR: usize = 0x13F;
+const CGB_FLAG_ADDR: usize = 0x143;
+const NEW_LICENSEE_ADDR: usize = 0x144;
+const SGB_FLAG_ADDR: usize = 0x146;
+const CARTRIDGE_TYPE_ADDR: usize = 0x147;
+const ROM_SIZE_ADDR: usize = 0x148;
+const RAM_SIZE_ADDR: usize = 0x149;
+const DESTINATION_ADDR: usize = 0x14A;
+const OLD_LICENSEE_ADDR: usize = 0x14B;
+const VERSION_ADDR: usize = 0x14C;
+const HEADER_CHECKSUM_ADDR: usize = 0x14D;
+const GLOBAL_CHECKSUM_ADDR: usize = 0x14E;
+pub(crate) const HEADER_END: usize = 0x150;
+
+// Cartridge types whose RAM (or EEPROM, on MBC7) is kept by a battery
+const BATTERY_TYPES: &[u8] = &[
+    0x03, 0x06, 0x09, 0x0D, 0x0F, 0x10, 0x13, 0x1B, 0x1E, 0x22, 0xFC, 0xFE, 0xFF,
+];
+
+// Old licensee code meaning the new licensee code should be used instead
+const USE_NEW_LICENSEE: u8 = 0x33;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CgbSupport {
+    #[default]
+    None,
+    Enhanced, // Also runs on DMG hardware
+    Only,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Destination {
+    #[default]
+    Japan,
+    Overseas,
+}
+
+// Mapper hardware named by the cartridge type byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapperKind {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mbc3,
+    Mbc5,
+    Mbc7,
+    PocketCamera,
+    HuC1,
+    HuC3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderError {
+    Truncated(usize), // ROM length, too short to hold a header
+    LogoMismatch,
+    HeaderChecksum { expected: u8, actual: u8 },
+    GlobalChecksum { expected: u16, actual: u16 },
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::Truncated(len) => {
+                write!(f, "ROM is {} bytes, too short to hold a header", len)
+            }
+            HeaderError::LogoMismatch => f.write_str("Nintendo logo doesn't match"),
+            HeaderError::HeaderChecksum { expected, actual } => write!(
+                f,
+                "header checksum is ${:02X}, but the header sums to ${:02X}",
+                expected, actual
+            ),
+            HeaderError::GlobalChecksum { expected, actual } => write!(
+                f,
+                "global checksum is ${:04X}, but the ROM sums to ${:04X}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for HeaderError {}
+
+/*
+    The cartridge header at 0x0100 -> 0x014F.
+    Newer carts shortened the title to make room for a manufacturer code and the CGB flag,
+    so those are only split out of the title when the CGB flag is set.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub manufacturer: Option<String>,
+    pub cgb_support: CgbSupport,
+    pub new_licensee: [u8; 2], // Two ASCII characters, only used when old_licensee is 0x33
+    pub old_licensee: u8,
+    pub sgb_support: bool,
+    pub cartridge_type: u8,
+    pub rom_size: Option<usize>, // None for size codes that don't exist
+    pub ram_size: usize,
+    pub destination: Destination,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+    logo_matches: bool,
+    computed_header_checksum: u8,
+    computed_global_checksum: u16,
 }
 
 impl CartridgeHeader {
-    pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+    pub fn parse(rom: &[u8]) -> Result<Self, HeaderError> {
+        if rom.len() < HEADER_END {
+            return Err(HeaderError::Truncated(rom.len()));
+        }
+
+        let cgb_support = match rom[CGB_FLAG_ADDR] {
+            0xC0 => CgbSupport::Only,
+            flag if flag & 0x80 != 0 => CgbSupport::Enhanced,
+            _ => CgbSupport::None,
+        };
+
+        let (title, manufacturer) = match cgb_support {
+            CgbSupport::None => (ascii(&rom[TITLE_ADDR..CGB_FLAG_ADDR + 1]), None),
+            _ => {
+                let code = &rom[MANUFACTURER_ADDR..CGB_FLAG_ADDR];
+                let manufacturer = code
+                    .iter()
+                    .all(u8::is_ascii_alphanumeric)
+                    .then(|| ascii(code));
+
+                (ascii(&rom[TITLE_ADDR..MANUFACTURER_ADDR]), manufacturer)
+            }
+        };
+
+        Ok(Self {
+            title,
+            manufacturer,
+            cgb_support,
+            new_licensee: [rom[NEW_LICENSEE_ADDR], rom[NEW_LICENSEE_ADDR + 1]],
+            old_licensee: rom[OLD_LICENSEE_ADDR],
+            sgb_support: rom[SGB_FLAG_ADDR] == 0x03,
+            cartridge_type: rom[CARTRIDGE_TYPE_ADDR],
+            rom_size: rom_size(rom[ROM_SIZE_ADDR]),
+            ram_size: ram_size(rom[RAM_SIZE_ADDR]),
+            destination: match rom[DESTINATION_ADDR] {
+                0x00 => Destination::Japan,
+                _ => Destination::Overseas,
+            },
+            version: rom[VERSION_ADDR],
+            header_checksum: rom[HEADER_CHECKSUM_ADDR],
+            global_checksum: u16::from_be_bytes([
+                rom[GLOBAL_CHECKSUM_ADDR],
+                rom[GLOBAL_CHECKSUM_ADDR + 1],
+            ]),
+            logo_matches: rom[LOGO_ADDR..LOGO_ADDR + NINTENDO_LOGO.len()] == NINTENDO_LOGO,
+            computed_header_checksum: header_checksum(rom),
+            computed_global_checksum: global_checksum(rom),
+        })
+    }
+
+    // The publisher's licensee code, as the 2 character new code when the old one defers to it
+    pub fn licensee(&self) -> String {
+        match self.old_licensee {
+            USE_NEW_LICENSEE => ascii(&self.new_licensee),
+            code => format!("{:02X}", code),
+        }
+    }
+
+    // None for cartridge types without a mapper implementation (e.g. MBC6, MMM01, TAMA5)
+    pub fn mapper(&self) -> Option<MapperKind> {
+        match self.cartridge_type {
+            0x00 | 0x08 | 0x09 => Some(MapperKind::RomOnly),
+            0x01..=0x03 => Some(MapperKind::Mbc1),
+            0x05 | 0x06 => Some(MapperKind::Mbc2),
+            0x0F..=0x13 => Some(MapperKind::Mbc3),
+            0x19..=0x1E => Some(MapperKind::Mbc5),
+            0x22 => Some(MapperKind::Mbc7),
+            0xFC => Some(MapperKind::PocketCamera),
+            0xFE => Some(MapperKind::HuC3),
+            0xFF => Some(MapperKind::HuC1),
+            _ => None,
+        }
+    }
+
+    pub fn has_battery(&self) -> bool {
+        BATTERY_TYPES.contains(&self.cartridge_type)
+    }
+
+    pub fn has_rtc(&self) -> bool {
+        matches!(self.cartridge_type, 0x0F | 0x10 | 0xFE)
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        matches!(self.cartridge_type, 0x1C..=0x1E)
+    }
+
+    // The boot ROM locks up unless this matches
+    pub fn check_logo(&self) -> Result<(), HeaderError> {
+        match self.logo_matches {
+            true => Ok(()),
+            false => Err(HeaderError::LogoMismatch),
+        }
+    }
+
+    // Also checked by the boot ROM
+    pub fn check_header_checksum(&self) -> Result<(), HeaderError> {
+        match self.header_checksum == self.computed_header_checksum {
+            true => Ok(()),
+            false => Err(HeaderError::HeaderChecksum {
+                expected: self.header_checksum,
+                actual: self.computed_header_checksum,
+            }),
+        }
+    }
+
+    // Never checked by hardware, and plenty of released games get it wrong
+    pub fn check_global_checksum(&self) -> Result<(), HeaderError> {
+        match self.global_checksum == self.computed_global_checksum {
+            true => Ok(()),
+            false => Err(HeaderError::GlobalChecksum {
+                expected: self.global_checksum,
+                actual: self.computed_global_checksum,
+            }),
+        }
+    }
+}
+
+// 0x0134 -> 0x014C, each byte subtracted along with 1
+pub(crate) fn header_checksum(rom: &[u8]) -> u8 {
+    rom[TITLE_ADDR..HEADER_CHECKSUM_ADDR]
+        .iter()
+        .fold(0u8, |checksum, byte| {
+            checksum.wrapping_sub(*byte).wrapping_sub(1)
+        })
+}
+
+// Every byte of the ROM except the global checksum itself
+pub(crate) fn global_checksum(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|(addr, _)| *addr != GLOBAL_CHECKSUM_ADDR && *addr != GLOBAL_CHECKSUM_ADDR + 1)
+        .fold(0u16, |checksum, (_, byte)| {
+            checksum.wrapping_add(*byte as u16)
+        })
+}
+
+// Text fields are padded with zeros, and anything past the first one is ignored
+fn ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+// 32 KiB doubled per step, plus three odd sizes listed in some old docs
+fn rom_size(code: u8) -> Option<usize> {
+    match code {
+        0x00..=0x08 => Some((2 * ROM_BANK_SIZE) << code),
+        0x52 => Some(72 * ROM_BANK_SIZE),
+        0x53 => Some(80 * ROM_BANK_SIZE),
+        0x54 => Some(96 * ROM_BANK_SIZE),
+        _ => None,
+    }
+}
+
+fn ram_size(code: u8) -> usize {
+    match code {
+        0x02 => RAM_BANK_SIZE,
+        0x03 => 4 * RAM_BANK_SIZE,
+        0x04 => 16 * RAM_BANK_SIZE,
+        0x05 => 8 * RAM_BANK_SIZE,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_rom() -> Vec<u8> {
+        let mut rom = vec![0; 2 * ROM_BANK_SIZE];
+        rom[LOGO_ADDR..LOGO_ADDR + 48].copy_from_slice(&NINTENDO_LOGO);
+        rom[TITLE_ADDR..TITLE_ADDR + 11].copy_from_slice(b"POCKETMONS\0");
+        rom[MANUFACTURER_ADDR..CGB_FLAG_ADDR].copy_from_slice(b"AAXE");
+        rom[CGB_FLAG_ADDR] = 0x80;
+        rom[NEW_LICENSEE_ADDR..NEW_LICENSEE_ADDR + 2].copy_from_slice(b"01");
+        rom[SGB_FLAG_ADDR] = 0x03;
+        rom[CARTRIDGE_TYPE_ADDR] = 0x10;
+        rom[ROM_SIZE_ADDR] = 0x06;
+        rom[RAM_SIZE_ADDR] = 0x03;
+        rom[DESTINATION_ADDR] = 0x01;
+        rom[OLD_LICENSEE_ADDR] = USE_NEW_LICENSEE;
+        rom[VERSION_ADDR] = 0x02;
+
+        rom[HEADER_CHECKSUM_ADDR] = header_checksum(&rom);
+        let checksum = global_checksum(&rom);
+        rom[GLOBAL_CHECKSUM_ADDR..HEADER_END].copy_from_slice(&checksum.to_be_bytes());
+
+        rom
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let header = CartridgeHeader::parse(&make_rom()).unwrap();
+
+        assert_eq!(header.title, "POCKETMONS");
+        assert_eq!(header.manufacturer.as_deref(), Some("AAXE"));
+        assert_eq!(header.cgb_support, CgbSupport::Enhanced);
+        assert_eq!(header.licensee(), "01");
+        assert!(header.sgb_support);
+        assert_eq!(header.mapper(), Some(MapperKind::Mbc3));
+        assert!(header.has_battery() && header.has_rtc() && !header.has_rumble());
+        assert_eq!(header.rom_size, Some(2 * 1024 * 1024));
+        assert_eq!(header.ram_size, 32 * 1024);
+        assert_eq!(header.destination, Destination::Overseas);
+        assert_eq!(header.version, 0x02);
+    }
+
+    #[test]
+    fn test_dmg_title_uses_all_16_bytes() {
+        let mut rom = make_rom();
+        rom[TITLE_ADDR..CGB_FLAG_ADDR + 1].copy_from_slice(b"SIXTEEN CHARS OK");
+
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.title, "SIXTEEN CHARS OK");
+        assert_eq!(header.manufacturer, None);
+        assert_eq!(header.cgb_support, CgbSupport::None);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut rom = make_rom();
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.check_logo(), Ok(()));
+        assert_eq!(header.check_header_checksum(), Ok(()));
+        assert_eq!(header.check_global_checksum(), Ok(()));
+
+        rom[LOGO_ADDR] ^= 0xFF;
+        rom[VERSION_ADDR] += 1;
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.check_logo(), Err(HeaderError::LogoMismatch));
+        assert!(matches!(
+            header.check_header_checksum(),
+            Err(HeaderError::HeaderChecksum { .. })
+        ));
+        assert!(matches!(
+            header.check_global_checksum(),
+            Err(HeaderError::GlobalChecksum { .. })
+        ));
+
+        assert_eq!(
+            CartridgeHeader::parse(&rom[..0x100]),
+            Err(HeaderError::Truncated(0x100))
+        );
     }
 }
@@ -0,0 +1,142 @@
+use super::infrared::{Infrared, InfraredPort};
+use super::mapper::{Mapper, Ram, Rom};
+
+/*
+    Hudson's HuC1 banks like a simplified MBC1, but has no RAM enable. Its first register
+    instead switches 0xA000 -> 0xBFFF between RAM and the infrared port (0x0E).
+*/
+pub struct HuC1 {
+    rom: Rom,
+    ram: Ram,
+    infrared: Infrared,
+    ir_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl HuC1 {
+    pub fn new(rom: Rom, ram: Ram) -> Self {
+        Self {
+            rom,
+            ram,
+            infrared: Infrared::new(),
+            ir_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+}
+
+impl Mapper for HuC1 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ir_mode = data & 0x0F == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = data & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = data & 0x03,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if self.ir_mode {
+            self.infrared.read()
+        } else {
+            self.ram.read(self.ram_bank as usize, addr)
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if self.ir_mode {
+            self.infrared.write(data);
+        } else {
+            self.ram.write(self.ram_bank as usize, addr, data);
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ => self.ram_bank as u16,
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.ram.data().to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.ram.load(data);
+    }
+
+    fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.infrared.set_port(port);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::cartridge::mapper::{RAM_BANK_SIZE, numbered_rom};
+
+    // Sees its own light, like two carts facing each other
+    struct Loopback(Rc<Cell<bool>>);
+
+    impl InfraredPort for Loopback {
+        fn set_led(&mut self, on: bool) {
+            self.0.set(on);
+        }
+
+        fn light_seen(&self) -> bool {
+            self.0.get()
+        }
+    }
+
+    fn make_huc1() -> HuC1 {
+        HuC1::new(numbered_rom(64), Ram::new(4 * RAM_BANK_SIZE))
+    }
+
+    #[test]
+    fn test_banking_without_ram_enable() {
+        let mut mbc = make_huc1();
+
+        mbc.write_rom(0x2000, 0x3F);
+        assert_eq!(mbc.read_rom(0x4000), 0x3F);
+
+        mbc.write_rom(0x4000, 0x02);
+        mbc.write_ram(0xA000, 0x22);
+        mbc.write_rom(0x4000, 0x00);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+
+        mbc.write_rom(0x4000, 0x02);
+        assert_eq!(mbc.read_ram(0xA000), 0x22);
+    }
+
+    #[test]
+    fn test_ir_mode() {
+        let mut mbc = make_huc1();
+        mbc.write_rom(0x0000, 0x0E);
+
+        assert_eq!(mbc.read_ram(0xA000), 0xC0);
+
+        let led = Rc::new(Cell::new(false));
+        mbc.set_infrared_port(Box::new(Loopback(led.clone())));
+
+        mbc.write_ram(0xA000, 0x01);
+        assert!(led.get());
+        assert_eq!(mbc.read_ram(0xA000), 0xC1);
+
+        // Back to RAM, which the IR write didn't touch
+        mbc.write_rom(0x0000, 0x0A);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+    }
+}
@@ -0,0 +1,412 @@
+use super::CartridgeEvent;
+use super::infrared::{Infrared, InfraredPort};
+use super::mapper::{Mapper, Ram, Rom};
+use super::rtc::{ClockSource, RtcClock};
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+// Where the clock is copied to and from in the RTC chip's memory, as 3 nibbles each, LSB first
+const MINUTES_ADDR: usize = 0x00;
+const DAYS_ADDR: usize = 0x03;
+
+// Seconds, minutes and days as 32 bit words, the memory one nibble per byte, then a 64 bit timestamp
+pub const HUC3_CLOCK_SAVE_SIZE: usize = 12 + 256 + 8;
+
+/*
+    The HuC3 clock only counts minutes of the day and days (12 bits each), with no seconds
+    visible to the game. Games talk to it through a nibble wide command interface and a small
+    memory, which the clock is latched into and set from.
+*/
+struct HuC3Clock {
+    source: ClockSource,
+    seconds: u8,
+    minutes: u16,
+    days: u16,
+    memory: [u8; 256], // Nibbles
+    addr: u8,
+    last_command: u8,
+    response: u8,
+}
+
+impl HuC3Clock {
+    fn new() -> Self {
+        Self {
+            source: ClockSource::new(),
+            seconds: 0,
+            minutes: 0,
+            days: 0,
+            memory: [0; 256],
+            addr: 0,
+            last_command: 0,
+            response: 0,
+        }
+    }
+
+    // Worked out per counter rather than a second at a time, since a save can be years old
+    fn advance(&mut self, seconds: u64) {
+        let seconds = self.seconds as u64 + seconds;
+        self.seconds = (seconds % 60) as u8;
+
+        let minutes = self.minutes as u64 + seconds / 60;
+        self.minutes = (minutes % MINUTES_PER_DAY as u64) as u16;
+
+        let days = self.days as u64 + minutes / MINUTES_PER_DAY as u64;
+        self.days = (days & 0xFFF) as u16;
+    }
+
+    fn store(&mut self, addr: usize, value: u16) {
+        for nibble in 0..3 {
+            self.memory[addr + nibble] = ((value >> (4 * nibble)) & 0x0F) as u8;
+        }
+    }
+
+    fn load(&self, addr: usize) -> u16 {
+        (0..3).fold(0, |value, nibble| {
+            value | ((self.memory[addr + nibble] as u16) << (4 * nibble))
+        })
+    }
+
+    // Like the MBC3 clock, the timestamp lets the clock catch up on the time the emulator was closed
+    fn save(&self) -> Vec<u8> {
+        let words = [self.seconds as u32, self.minutes as u32, self.days as u32];
+
+        words
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .chain(self.memory)
+            .chain(self.source.timestamp().to_le_bytes())
+            .collect()
+    }
+
+    fn restore(&mut self, data: &[u8]) {
+        if data.len() != HUC3_CLOCK_SAVE_SIZE {
+            return;
+        }
+
+        let word = |i: usize| u32::from_le_bytes(data[i * 4..i * 4 + 4].try_into().unwrap());
+        self.seconds = (word(0) % 60) as u8;
+        self.minutes = (word(1) % MINUTES_PER_DAY as u32) as u16;
+        self.days = (word(2) & 0xFFF) as u16;
+
+        for (nibble, byte) in self.memory.iter_mut().zip(&data[12..268]) {
+            *nibble = byte & 0x0F;
+        }
+
+        self.source
+            .restore(u64::from_le_bytes(data[268..].try_into().unwrap()));
+    }
+
+    // Commands are the upper bits and their argument the low nibble
+    fn command(&mut self, data: u8, events: &mut Vec<CartridgeEvent>) {
+        let command = (data >> 4) & 0x07;
+        let argument = data & 0x0F;
+
+        match command {
+            0x1 => {
+                self.response = self.memory[self.addr as usize];
+                self.addr = self.addr.wrapping_add(1);
+            }
+            0x3 => {
+                self.memory[self.addr as usize] = argument;
+                self.addr = self.addr.wrapping_add(1);
+            }
+            0x4 => self.addr = (self.addr & 0xF0) | argument,
+            0x5 => self.addr = (self.addr & 0x0F) | (argument << 4),
+            0x6 => match argument {
+                0x0 => {
+                    let elapsed = self.source.host_seconds();
+                    self.advance(elapsed);
+                    self.store(MINUTES_ADDR, self.minutes);
+                    self.store(DAYS_ADDR, self.days);
+                }
+                0x1 => {
+                    self.source.host_seconds(); // Time before the new value was set doesn't count
+                    self.seconds = 0;
+                    self.minutes = self.load(MINUTES_ADDR) % MINUTES_PER_DAY;
+                    self.days = self.load(DAYS_ADDR);
+                }
+                0x2 => self.response = 0x1, // Status, always reports a working clock
+                0xE => events.push(CartridgeEvent::Tone),
+                _ => {}
+            },
+            _ => {}
+        }
+
+        self.last_command = command;
+    }
+
+    fn read_response(&self) -> u8 {
+        0x80 | (self.last_command << 4) | self.response
+    }
+}
+
+/*
+    Hudson's HuC3 adds the clock, a speaker and an infrared port to MBC style banking.
+    The low nibble of the first register selects what 0xA000 -> 0xBFFF is connected to:
+    0x0 RAM (read only), 0xA RAM, 0xB clock commands, 0xC clock responses,
+    0xD the clock's ready flag and 0xE the infrared port.
+*/
+pub struct HuC3 {
+    rom: Rom,
+    ram: Ram,
+    clock: HuC3Clock,
+    infrared: Infrared,
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+    events: Vec<CartridgeEvent>,
+}
+
+impl HuC3 {
+    pub fn new(rom: Rom, ram: Ram) -> Self {
+        Self {
+            rom,
+            ram,
+            clock: HuC3Clock::new(),
+            infrared: Infrared::new(),
+            mode: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl Mapper for HuC3 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.mode = data & 0x0F,
+            0x2000..=0x3FFF => self.rom_bank = data & 0x7F,
+            0x4000..=0x5FFF => self.ram_bank = data & 0x03,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        match self.mode {
+            0x0 | 0xA => self.ram.read(self.ram_bank as usize, addr),
+            0xC => self.clock.read_response(),
+            0xD => 0x01, // Commands finish instantly, so the clock is always ready
+            0xE => self.infrared.read(),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        match self.mode {
+            0xA => self.ram.write(self.ram_bank as usize, addr, data),
+            0xB => self.clock.command(data, &mut self.events),
+            0xE => self.infrared.write(data),
+            _ => {}
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ => self.ram_bank as u16,
+        }
+    }
+
+    // RAM, then the clock. Emulators don't agree on a layout for the clock, so this one is our own
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let mut data = self.ram.data().to_vec();
+        data.extend(self.clock.save());
+
+        Some(data)
+    }
+
+    fn clock_save_size(&self) -> usize {
+        HUC3_CLOCK_SAVE_SIZE
+    }
+
+    // Saves without the clock are just RAM
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = self.ram.data().len().min(data.len());
+        self.ram.load(&data[..ram_size]);
+        self.clock.restore(&data[ram_size..]);
+    }
+
+    fn tick(&mut self, t_cycles: u8) {
+        if self.clock.source.tick(t_cycles) {
+            self.clock.advance(1);
+        }
+    }
+
+    fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.clock.source.set_clock(clock);
+    }
+
+    fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.infrared.set_port(port);
+    }
+
+    fn drain_events(&mut self, events: &mut Vec<CartridgeEvent>) {
+        events.append(&mut self.events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+    use crate::cartridge::rtc::unix_time;
+
+    fn make_huc3() -> HuC3 {
+        HuC3::new(
+            Rom::new(vec![0; 4 * ROM_BANK_SIZE]),
+            Ram::new(4 * RAM_BANK_SIZE),
+        )
+    }
+
+    fn command(mbc: &mut HuC3, command: u8, argument: u8) {
+        mbc.write_rom(0x0000, 0x0B);
+        mbc.write_ram(0xA000, (command << 4) | argument);
+    }
+
+    fn response(mbc: &mut HuC3) -> u8 {
+        mbc.write_rom(0x0000, 0x0C);
+        mbc.read_ram(0xA000) & 0x0F
+    }
+
+    fn set_addr(mbc: &mut HuC3, addr: u8) {
+        command(mbc, 0x4, addr & 0x0F);
+        command(mbc, 0x5, addr >> 4);
+    }
+
+    #[test]
+    fn test_set_and_latch_clock() {
+        let mut mbc = make_huc3();
+
+        // 23:59 on day 0x123
+        set_addr(&mut mbc, 0x00);
+        for nibble in [0xF, 0x9, 0x5, 0x3, 0x2, 0x1] {
+            command(&mut mbc, 0x3, nibble);
+        }
+        command(&mut mbc, 0x6, 0x1);
+
+        mbc.clock.advance(60);
+        command(&mut mbc, 0x6, 0x0);
+
+        set_addr(&mut mbc, 0x00);
+        let nibbles: Vec<u8> = (0..6)
+            .map(|_| {
+                command(&mut mbc, 0x1, 0);
+                response(&mut mbc)
+            })
+            .collect();
+
+        assert_eq!(nibbles, [0, 0, 0, 0x4, 0x2, 0x1]);
+    }
+
+    #[test]
+    fn test_clock_survives_save() {
+        let mut mbc = make_huc3();
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA000, 0x42);
+
+        set_addr(&mut mbc, 0x10);
+        command(&mut mbc, 0x3, 0x7); // Games keep their own settings in the clock's memory
+        mbc.clock.minutes = 123;
+        mbc.clock.days = 45;
+
+        let save = mbc.save_data().unwrap();
+        assert_eq!(save.len(), 4 * RAM_BANK_SIZE + HUC3_CLOCK_SAVE_SIZE);
+
+        let mut restored = make_huc3();
+        restored.load_save_data(&save);
+        restored.write_rom(0x0000, 0x0A);
+
+        assert_eq!(restored.read_ram(0xA000), 0x42);
+        assert_eq!(restored.clock.memory[0x10], 0x7);
+        assert_eq!((restored.clock.minutes, restored.clock.days), (123, 45));
+
+        // RAM only saves leave the clock alone
+        let mut ram_only = make_huc3();
+        ram_only.load_save_data(&save[..4 * RAM_BANK_SIZE]);
+        assert_eq!(ram_only.clock.minutes, 0);
+    }
+
+    #[test]
+    fn test_advance_carries_into_days() {
+        let mut mbc = make_huc3();
+        mbc.clock.seconds = 59;
+        mbc.clock.minutes = MINUTES_PER_DAY - 1;
+        mbc.clock.days = 0xFFF;
+
+        mbc.clock.advance(1);
+        assert_eq!(
+            (mbc.clock.seconds, mbc.clock.minutes, mbc.clock.days),
+            (0, 0, 0)
+        );
+
+        mbc.clock.advance(2 * 24 * 3600 + 61);
+        assert_eq!(
+            (mbc.clock.seconds, mbc.clock.minutes, mbc.clock.days),
+            (1, 1, 2)
+        );
+    }
+
+    #[test]
+    fn test_save_keeps_host_time_since_the_last_latch() {
+        let mut mbc = make_huc3();
+        mbc.set_rtc_clock(RtcClock::Host);
+
+        // An hour passes without the game latching before the save is written
+        mbc.clock.source.rewind(3600);
+        let save = mbc.save_data().unwrap();
+
+        let mut restored = make_huc3();
+        restored.set_rtc_clock(RtcClock::Host);
+        restored.load_save_data(&save);
+        command(&mut restored, 0x6, 0x0);
+
+        assert_eq!(restored.clock.minutes, 60);
+    }
+
+    #[test]
+    fn test_load_with_zero_timestamp() {
+        let mut save = make_huc3().save_data().unwrap();
+        let len = save.len();
+        save[len - 8..].copy_from_slice(&0u64.to_le_bytes());
+
+        let mut mbc = make_huc3();
+        mbc.set_rtc_clock(RtcClock::Host);
+        mbc.load_save_data(&save);
+        command(&mut mbc, 0x6, 0x0);
+
+        let days = (unix_time() / (24 * 3600)) & 0xFFF;
+        assert_eq!(mbc.clock.days as u64, days);
+    }
+
+    #[test]
+    fn test_modes_and_tone() {
+        let mut mbc = make_huc3();
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA000, 0x5A);
+        mbc.write_rom(0x0000, 0x00);
+        mbc.write_ram(0xA000, 0x00); // Read only
+        assert_eq!(mbc.read_ram(0xA000), 0x5A);
+
+        mbc.write_rom(0x0000, 0x0D);
+        assert_eq!(mbc.read_ram(0xA000), 0x01);
+
+        mbc.write_rom(0x0000, 0x0E);
+        assert_eq!(mbc.read_ram(0xA000), 0xC0);
+
+        command(&mut mbc, 0x6, 0xE);
+
+        let mut events = Vec::new();
+        mbc.drain_events(&mut events);
+        assert_eq!(events, [CartridgeEvent::Tone]);
+    }
+}
@@ -0,0 +1,46 @@
+/*
+    The other end of a cartridge's infrared LED and sensor, e.g. another emulator instance,
+    a TV remote recording or a test script.
+*/
+pub trait InfraredPort {
+    // The cartridge turned its LED on or off
+    fn set_led(&mut self, _on: bool) {}
+
+    // Whether the cartridge's sensor currently sees light
+    fn light_seen(&self) -> bool {
+        false
+    }
+}
+
+// Nothing on the other end: the LED goes nowhere and no light is ever seen
+pub struct NoInfrared;
+
+impl InfraredPort for NoInfrared {}
+
+/*
+    What HuC1 and HuC3 carts show in place of RAM while in IR mode.
+    Bit 0 reads the sensor, writes to bit 0 drive the LED, and the other bits read as 0xC0.
+*/
+pub struct Infrared {
+    port: Box<dyn InfraredPort>,
+}
+
+impl Infrared {
+    pub fn new() -> Self {
+        Self {
+            port: Box::new(NoInfrared),
+        }
+    }
+
+    pub fn set_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.port = port;
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.port.light_seen() as u8
+    }
+
+    pub fn write(&mut self, data: u8) {
+        self.port.set_led(data & 0x01 != 0);
+    }
+}
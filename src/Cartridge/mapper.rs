@@ -0,0 +1,172 @@
+use super::CartridgeEvent;
+use super::camera::ImageSource;
+use super::infrared::InfraredPort;
+use super::rtc::RtcClock;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+/*
+    The cartridge side of the bus: ROM at 0x0000 -> 0x7FFF and external RAM at 0xA000 -> 0xBFFF.
+    Writes to the ROM area don't change ROM, they program the mapper's bank registers.
+*/
+pub trait Mapper {
+    fn read_rom(&self, addr: u16) -> u8;
+    fn write_rom(&mut self, addr: u16, data: u8);
+    fn read_ram(&self, addr: u16) -> u8;
+    fn write_ram(&mut self, addr: u16, data: u8);
+    // Bank currently mapped at addr, in either the ROM or RAM window
+    fn bank(&self, addr: u16) -> u16;
+
+    // Called every M-cycle, which lasts `t_cycles` of the 4 MiHz clock (2 in CGB double speed)
+    fn tick(&mut self, _t_cycles: u8) {}
+
+    fn set_rtc_clock(&mut self, _clock: RtcClock) {}
+
+    // What's on the other end of the cart's infrared LED and sensor, for carts that have them
+    fn set_infrared_port(&mut self, _port: Box<dyn InfraredPort>) {}
+
+    // Where pictures come from, for carts with a camera
+    fn set_image_source(&mut self, _source: Box<dyn ImageSource>) {}
+
+    // Accelerometer input in g, for carts that have one
+    fn set_tilt(&mut self, _x: f32, _y: f32) {}
+
+    // Memory that survives power off in the raw layout of a .sav file, None if there's nothing to keep
+    fn save_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    // Bytes at the end of save_data holding a clock, which changes every second on its own
+    fn clock_save_size(&self) -> usize {
+        0
+    }
+
+    // Restores memory from save_data's layout, extra or missing bytes are ignored
+    fn load_save_data(&mut self, _data: &[u8]) {}
+
+    // Moves anything the cartridge hardware did since the last call into `events`
+    fn drain_events(&mut self, _events: &mut Vec<CartridgeEvent>) {}
+}
+
+pub struct Rom {
+    data: Vec<u8>,
+    bank_mask: usize, // Bank numbers wrap around at the ROM size, like the unconnected address lines
+}
+
+impl Rom {
+    pub fn new(data: Vec<u8>) -> Self {
+        let banks = data
+            .len()
+            .div_ceil(ROM_BANK_SIZE)
+            .max(1)
+            .next_power_of_two();
+
+        Self {
+            data,
+            bank_mask: banks - 1,
+        }
+    }
+
+    pub fn read(&self, bank: usize, addr: u16) -> u8 {
+        let offset =
+            (bank & self.bank_mask) * ROM_BANK_SIZE + (addr as usize & (ROM_BANK_SIZE - 1));
+
+        self.data.get(offset).copied().unwrap_or(0xFF)
+    }
+}
+
+// Each bank starts with its own number (low byte, then high byte), so tests can tell which is mapped
+#[cfg(test)]
+pub fn numbered_rom(banks: usize) -> Rom {
+    let mut data = vec![0; banks * ROM_BANK_SIZE];
+    for bank in 0..banks {
+        data[bank * ROM_BANK_SIZE] = bank as u8;
+        data[bank * ROM_BANK_SIZE + 1] = (bank >> 8) as u8;
+    }
+
+    Rom::new(data)
+}
+
+pub struct Ram {
+    data: Vec<u8>,
+}
+
+impl Ram {
+    pub fn new(size: usize) -> Self {
+        Self {
+            data: vec![0; size],
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    // Copies in as much of `data` as fits, leaving the rest of RAM as it was
+    pub fn load(&mut self, data: &[u8]) {
+        let len = data.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&data[..len]);
+    }
+
+    // Chips smaller than a bank (e.g. 2 KiB) repeat across it
+    fn offset(&self, bank: usize, addr: u16) -> Option<usize> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some((bank * RAM_BANK_SIZE + (addr as usize & (RAM_BANK_SIZE - 1))) % self.data.len())
+    }
+
+    // Reads without RAM behind them see an open bus
+    pub fn read(&self, bank: usize, addr: u16) -> u8 {
+        self.offset(bank, addr)
+            .map_or(0xFF, |offset| self.data[offset])
+    }
+
+    pub fn write(&mut self, bank: usize, addr: u16, data: u8) {
+        if let Some(offset) = self.offset(bank, addr) {
+            self.data[offset] = data;
+        }
+    }
+}
+
+// Cartridges with no mapper, optionally with up to 8 KiB of RAM
+pub struct RomOnly {
+    rom: Rom,
+    ram: Ram,
+}
+
+impl RomOnly {
+    pub fn new(rom: Rom, ram: Ram) -> Self {
+        Self { rom, ram }
+    }
+}
+
+impl Mapper for RomOnly {
+    fn read_rom(&self, addr: u16) -> u8 {
+        self.rom.read((addr as usize) / ROM_BANK_SIZE, addr)
+    }
+
+    fn write_rom(&mut self, _addr: u16, _data: u8) {}
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        self.ram.read(0, addr)
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        self.ram.write(0, addr, data);
+    }
+
+    fn bank(&self, _addr: u16) -> u16 {
+        0
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.ram.data().to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.ram.load(data);
+    }
+}
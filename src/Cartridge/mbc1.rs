@@ -0,0 +1,169 @@
+use super::mapper::{Mapper, Ram, Rom};
+
+/*
+    MBC1 has a 5 bit ROM bank register (BANK1) and a 2 bit register (BANK2) that either
+    extends the ROM bank number or selects the RAM bank. In mode 0 BANK2 only applies to the
+    switchable ROM window; in mode 1 it also applies to the 0x0000 window and to RAM.
+
+    MBC1M multicarts wire BANK2 one bit lower and leave BANK1's top bit unconnected,
+    so each game sees 16 banks of 16 KiB.
+*/
+pub struct Mbc1 {
+    rom: Rom,
+    ram: Ram,
+    ram_enabled: bool,
+    bank1: u8,
+    bank2: u8,
+    mode: bool,
+    bank2_shift: u8, // 5 for MBC1, 4 for MBC1M
+}
+
+impl Mbc1 {
+    pub fn new(rom: Rom, ram: Ram, multicart: bool) -> Self {
+        Self {
+            rom,
+            ram,
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            mode: false,
+            bank2_shift: if multicart { 4 } else { 5 },
+        }
+    }
+
+    fn low_rom_bank(&self) -> usize {
+        if self.mode {
+            (self.bank2 as usize) << self.bank2_shift
+        } else {
+            0
+        }
+    }
+
+    fn high_rom_bank(&self) -> usize {
+        let bank1 = self.bank1 as usize & ((1 << self.bank2_shift) - 1);
+
+        ((self.bank2 as usize) << self.bank2_shift) | bank1
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.mode { self.bank2 as usize } else { 0 }
+    }
+}
+
+impl Mapper for Mbc1 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(self.low_rom_bank(), addr),
+            _ => self.rom.read(self.high_rom_bank(), addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = data & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                // The zero check looks at all 5 bits, which is why banks 0x20, 0x40 and 0x60 are unreachable
+                self.bank1 = match data & 0x1F {
+                    0 => 1,
+                    bank => bank,
+                }
+            }
+            0x4000..=0x5FFF => self.bank2 = data & 0x03,
+            _ => self.mode = data & 0x01 != 0,
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        self.ram.read(self.ram_bank(), addr)
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if self.ram_enabled {
+            self.ram.write(self.ram_bank(), addr, data);
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => self.low_rom_bank() as u16,
+            0x4000..=0x7FFF => self.high_rom_bank() as u16,
+            _ => self.ram_bank() as u16,
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.ram.data().to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.ram.load(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::{RAM_BANK_SIZE, numbered_rom};
+
+    // Each bank starts with its own number
+    #[test]
+    fn test_rom_banking() {
+        let mut mbc = Mbc1::new(numbered_rom(128), Ram::new(0), false);
+
+        assert_eq!(mbc.read_rom(0x4000), 1);
+
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 1);
+
+        mbc.write_rom(0x2000, 0x05);
+        mbc.write_rom(0x4000, 0x02);
+        assert_eq!(mbc.read_rom(0x4000), 0x45);
+        assert_eq!(mbc.read_rom(0x0000), 0);
+
+        // Mode 1 maps BANK2 into the lower window too
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_rom(0x0000), 0x40);
+
+        // Zeroing BANK1 with BANK2 set gives bank 0x41, not 0x40
+        mbc.write_rom(0x2000, 0x20);
+        assert_eq!(mbc.read_rom(0x4000), 0x41);
+    }
+
+    #[test]
+    fn test_ram_enable_and_banking() {
+        let mut mbc = Mbc1::new(numbered_rom(4), Ram::new(4 * RAM_BANK_SIZE), false);
+
+        mbc.write_ram(0xA000, 0x11);
+        assert_eq!(mbc.read_ram(0xA000), 0xFF);
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA000, 0x11);
+        mbc.write_rom(0x4000, 0x02);
+
+        // BANK2 only selects the RAM bank in mode 1
+        assert_eq!(mbc.read_ram(0xA000), 0x11);
+
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+
+        mbc.write_ram(0xA000, 0x22);
+        mbc.write_rom(0x4000, 0x00);
+        assert_eq!(mbc.read_ram(0xA000), 0x11);
+    }
+
+    #[test]
+    fn test_multicart_wiring() {
+        let mut mbc = Mbc1::new(numbered_rom(64), Ram::new(0), true);
+
+        mbc.write_rom(0x2000, 0x12); // Bit 4 isn't connected
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(mbc.read_rom(0x4000), 0x12);
+
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_rom(0x0000), 0x10);
+    }
+}
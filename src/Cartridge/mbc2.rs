@@ -0,0 +1,130 @@
+use super::mapper::{Mapper, Rom};
+
+const RAM_SIZE: usize = 512;
+
+/*
+    MBC2 has 512 half-bytes of RAM built in, repeated across the whole 0xA000 -> 0xBFFF window.
+    Its registers both live in 0x0000 -> 0x3FFF, with address bit 8 choosing between
+    RAM enable (clear) and the 4 bit ROM bank (set).
+*/
+pub struct Mbc2 {
+    rom: Rom,
+    ram: [u8; RAM_SIZE], // Lower nibbles only
+    ram_enabled: bool,
+    rom_bank: u8,
+    has_battery: bool,
+}
+
+impl Mbc2 {
+    pub fn new(rom: Rom, has_battery: bool) -> Self {
+        Self {
+            rom,
+            ram: [0; RAM_SIZE],
+            ram_enabled: false,
+            rom_bank: 1,
+            has_battery,
+        }
+    }
+}
+
+impl Mapper for Mbc2 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x3FFF if addr & 0x100 == 0 => self.ram_enabled = data & 0x0F == 0x0A,
+            0x0000..=0x3FFF => {
+                self.rom_bank = match data & 0x0F {
+                    0 => 1,
+                    bank => bank,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The upper nibble isn't connected, so it reads as 1s
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        0xF0 | self.ram[addr as usize % RAM_SIZE]
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if self.ram_enabled {
+            self.ram[addr as usize % RAM_SIZE] = data & 0x0F;
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ => 0,
+        }
+    }
+
+    // One byte per nibble, which is how other emulators lay out MBC2 saves
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.has_battery.then(|| self.ram.to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        for (nibble, byte) in self.ram.iter_mut().zip(data) {
+            *nibble = byte & 0x0F;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::numbered_rom;
+
+    fn make_mbc2() -> Mbc2 {
+        Mbc2::new(numbered_rom(16), true)
+    }
+
+    #[test]
+    fn test_address_bit_8_selects_register() {
+        let mut mbc = make_mbc2();
+
+        mbc.write_rom(0x0100, 0x0A); // ROM bank, not RAM enable
+        assert_eq!(mbc.read_rom(0x4000), 0x0A);
+        assert_eq!(mbc.read_ram(0xA000), 0xFF);
+
+        mbc.write_rom(0x3E00, 0x0A);
+        mbc.write_rom(0x2100, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 1);
+
+        mbc.write_ram(0xA000, 0x00);
+        assert_eq!(mbc.read_ram(0xA000), 0xF0);
+    }
+
+    #[test]
+    fn test_ram_is_mirrored_nibbles() {
+        let mut mbc = make_mbc2();
+        mbc.write_rom(0x0000, 0x0A);
+
+        mbc.write_ram(0xA005, 0xAB);
+
+        assert_eq!(mbc.read_ram(0xA005), 0xFB);
+        assert_eq!(mbc.read_ram(0xA205), 0xFB);
+        assert_eq!(mbc.read_ram(0xBE05), 0xFB);
+
+        let save = mbc.save_data().unwrap();
+        assert_eq!(save.len(), 512);
+        assert_eq!(save[5], 0x0B);
+
+        let mut restored = make_mbc2();
+        restored.load_save_data(&save);
+        restored.write_rom(0x0000, 0x0A);
+        assert_eq!(restored.read_ram(0xA005), 0xFB);
+    }
+}
@@ -0,0 +1,231 @@
+use super::mapper::{Mapper, Ram, Rom};
+use super::rtc::{RTC_SAVE_SIZE, Rtc, RtcClock};
+
+/*
+    MBC3 has a 7 bit ROM bank and up to 4 RAM banks. The RAM bank register also selects the
+    RTC registers (0x08 -> 0x0C), which then appear in place of RAM.
+    MBC30, used by the Japanese Pokémon Crystal, is the same chip with one more bit in each bank register.
+*/
+pub struct Mbc3 {
+    rom: Rom,
+    ram: Ram,
+    rtc: Option<Rtc>,
+    ram_enabled: bool, // Also gates the RTC registers
+    rom_bank: u8,
+    ram_bank: u8, // Or RTC register
+    rom_bank_mask: u8,
+    ram_bank_mask: u8,
+    latch_armed: bool, // Writing 0 then 1 to 0x6000 -> 0x7FFF latches the clock
+}
+
+impl Mbc3 {
+    pub fn new(rom: Rom, ram: Ram, has_rtc: bool, mbc30: bool) -> Self {
+        Self {
+            rom,
+            ram,
+            rtc: has_rtc.then(Rtc::new),
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rom_bank_mask: if mbc30 { 0xFF } else { 0x7F },
+            ram_bank_mask: if mbc30 { 0x07 } else { 0x03 },
+            latch_armed: false,
+        }
+    }
+}
+
+impl Mapper for Mbc3 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = data & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                self.rom_bank = match data & self.rom_bank_mask {
+                    0 => 1,
+                    bank => bank,
+                }
+            }
+            0x4000..=0x5FFF => self.ram_bank = data,
+            _ => {
+                if self.latch_armed
+                    && data == 0x01
+                    && let Some(rtc) = &mut self.rtc
+                {
+                    rtc.latch();
+                }
+
+                self.latch_armed = data == 0x00;
+            }
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        match (self.ram_bank, &self.rtc) {
+            (0x08..=0x0C, Some(rtc)) => rtc.read(self.ram_bank),
+            (bank, _) if bank <= self.ram_bank_mask => self.ram.read(bank as usize, addr),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+
+        match (self.ram_bank, &mut self.rtc) {
+            (0x08..=0x0C, Some(rtc)) => rtc.write(self.ram_bank, data),
+            (bank, _) if bank <= self.ram_bank_mask => self.ram.write(bank as usize, addr, data),
+            _ => {}
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ => self.ram_bank as u16,
+        }
+    }
+
+    // RAM, followed by the clock for carts that have one
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let mut data = self.ram.data().to_vec();
+        if let Some(rtc) = &self.rtc {
+            data.extend(rtc.save());
+        }
+
+        Some(data)
+    }
+
+    fn clock_save_size(&self) -> usize {
+        if self.rtc.is_some() { RTC_SAVE_SIZE } else { 0 }
+    }
+
+    // Saves from emulators that don't keep the clock are just RAM
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = self.ram.data().len().min(data.len());
+        self.ram.load(&data[..ram_size]);
+
+        if let Some(rtc) = &mut self.rtc {
+            rtc.load(&data[ram_size..]);
+        }
+    }
+
+    fn tick(&mut self, t_cycles: u8) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.tick(t_cycles);
+        }
+    }
+
+    fn set_rtc_clock(&mut self, clock: RtcClock) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.set_clock(clock);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::{RAM_BANK_SIZE, numbered_rom};
+
+    fn make_mbc3(rom_banks: usize, mbc30: bool) -> Mbc3 {
+        Mbc3::new(
+            numbered_rom(rom_banks),
+            Ram::new(8 * RAM_BANK_SIZE),
+            true,
+            mbc30,
+        )
+    }
+
+    #[test]
+    fn test_rom_and_ram_banking() {
+        let mut mbc = make_mbc3(256, false);
+
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 1);
+
+        // Only 7 bits are wired on MBC3
+        mbc.write_rom(0x2000, 0xC5);
+        assert_eq!(mbc.read_rom(0x4000), 0x45);
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x03);
+        mbc.write_ram(0xA000, 0x33);
+        mbc.write_rom(0x4000, 0x00);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+
+        mbc.write_rom(0x4000, 0x03);
+        assert_eq!(mbc.read_ram(0xA000), 0x33);
+
+        // Banks 4-7 only exist on MBC30
+        mbc.write_rom(0x4000, 0x04);
+        assert_eq!(mbc.read_ram(0xA000), 0xFF);
+
+        let mut mbc30 = make_mbc3(256, true);
+        mbc30.write_rom(0x2000, 0xC5);
+        assert_eq!(mbc30.read_rom(0x4000), 0xC5);
+
+        mbc30.write_rom(0x0000, 0x0A);
+        mbc30.write_rom(0x4000, 0x07);
+        mbc30.write_ram(0xA000, 0x77);
+        assert_eq!(mbc30.read_ram(0xA000), 0x77);
+    }
+
+    #[test]
+    fn test_rtc_registers_need_latch() {
+        let mut mbc = make_mbc3(4, false);
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x09); // Minutes
+
+        mbc.write_ram(0xA000, 42);
+        mbc.write_rom(0x4000, 0x08); // Seconds
+        mbc.write_ram(0xA000, 59);
+
+        for _ in 0..1024 * 1024 {
+            mbc.tick(4);
+        }
+        assert_eq!(mbc.read_ram(0xA000), 59);
+
+        mbc.write_rom(0x6000, 0x00);
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_ram(0xA000), 0);
+
+        mbc.write_rom(0x4000, 0x09);
+        assert_eq!(mbc.read_ram(0xA000), 43);
+    }
+
+    #[test]
+    fn test_save_has_ram_then_clock() {
+        let mut mbc = make_mbc3(4, false);
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x01);
+        mbc.write_ram(0xA000, 0x42);
+        mbc.write_rom(0x4000, 0x09);
+        mbc.write_ram(0xA000, 30); // Minutes
+
+        let save = mbc.save_data().unwrap();
+        assert_eq!(save.len(), 8 * RAM_BANK_SIZE + RTC_SAVE_SIZE);
+
+        let mut restored = make_mbc3(4, false);
+        restored.load_save_data(&save);
+        restored.write_rom(0x0000, 0x0A);
+        restored.write_rom(0x6000, 0x00);
+        restored.write_rom(0x6000, 0x01);
+        restored.write_rom(0x4000, 0x09);
+        assert_eq!(restored.read_ram(0xA000), 30);
+
+        restored.write_rom(0x4000, 0x01);
+        assert_eq!(restored.read_ram(0xA000), 0x42);
+    }
+}
@@ -0,0 +1,162 @@
+use super::CartridgeEvent;
+use super::mapper::{Mapper, Ram, Rom};
+
+/*
+    MBC5 splits a 9 bit ROM bank number across two registers, and unlike earlier MBCs
+    bank 0 can be mapped into the switchable window. Rumble carts take bit 3 of the
+    RAM bank register to drive the motor, leaving them 8 RAM banks.
+*/
+pub struct Mbc5 {
+    rom: Rom,
+    ram: Ram,
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+    rumble: Option<bool>, // Motor state, for carts that have one
+    events: Vec<CartridgeEvent>,
+}
+
+impl Mbc5 {
+    pub fn new(rom: Rom, ram: Ram, has_rumble: bool) -> Self {
+        Self {
+            rom,
+            ram,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rumble: has_rumble.then_some(false),
+            events: Vec::new(),
+        }
+    }
+
+    fn set_ram_bank(&mut self, data: u8) {
+        let Some(rumble) = self.rumble else {
+            self.ram_bank = data & 0x0F;
+            return;
+        };
+
+        self.ram_bank = data & 0x07;
+
+        let motor = data & 0x08 != 0;
+        if motor != rumble {
+            self.rumble = Some(motor);
+            self.events.push(CartridgeEvent::Rumble(motor));
+        }
+    }
+}
+
+impl Mapper for Mbc5 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = data == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | data as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((data as u16 & 0x01) << 8),
+            0x4000..=0x5FFF => self.set_ram_bank(data),
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        self.ram.read(self.ram_bank as usize, addr)
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if self.ram_enabled {
+            self.ram.write(self.ram_bank as usize, addr, data);
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank,
+            _ => self.ram_bank as u16,
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.ram.data().to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.ram.load(data);
+    }
+
+    fn drain_events(&mut self, events: &mut Vec<CartridgeEvent>) {
+        events.append(&mut self.events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::{RAM_BANK_SIZE, numbered_rom};
+
+    fn make_mbc5(rom_banks: usize, ram_banks: usize, has_rumble: bool) -> Mbc5 {
+        Mbc5::new(
+            numbered_rom(rom_banks),
+            Ram::new(ram_banks * RAM_BANK_SIZE),
+            has_rumble,
+        )
+    }
+
+    #[test]
+    fn test_nine_bit_rom_banks() {
+        let mut mbc = make_mbc5(512, 0, false);
+
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0); // Bank 0 isn't remapped to 1
+
+        mbc.write_rom(0x2000, 0x23);
+        mbc.write_rom(0x3000, 0x01);
+        assert_eq!([mbc.read_rom(0x4000), mbc.read_rom(0x4001)], [0x23, 0x01]);
+    }
+
+    #[test]
+    fn test_ram_banks() {
+        let mut mbc = make_mbc5(4, 16, false);
+        mbc.write_rom(0x0000, 0x0A);
+
+        mbc.write_rom(0x4000, 0x0F);
+        mbc.write_ram(0xA000, 0xF0);
+        mbc.write_rom(0x4000, 0x07);
+        mbc.write_ram(0xA000, 0x70);
+
+        mbc.write_rom(0x4000, 0x0F);
+        assert_eq!(mbc.read_ram(0xA000), 0xF0);
+    }
+
+    #[test]
+    fn test_rumble_reports_motor_changes() {
+        let mut mbc = make_mbc5(4, 8, true);
+        mbc.write_rom(0x0000, 0x0A);
+
+        mbc.write_rom(0x4000, 0x01);
+        mbc.write_ram(0xA000, 0x11);
+
+        mbc.write_rom(0x4000, 0x09);
+        mbc.write_rom(0x4000, 0x09);
+        assert_eq!(mbc.read_ram(0xA000), 0x11); // Bit 3 doesn't select a RAM bank
+
+        mbc.write_rom(0x4000, 0x01);
+
+        let mut events = Vec::new();
+        mbc.drain_events(&mut events);
+
+        assert_eq!(
+            events,
+            [CartridgeEvent::Rumble(true), CartridgeEvent::Rumble(false)]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use super::mapper::{Mapper, Rom};
+
+// Accelerometer reading when level, and roughly how far it moves per g
+const TILT_CENTER: f32 = 0x81D0 as f32;
+const TILT_PER_G: f32 = 0x70 as f32;
+const TILT_ERASED: u16 = 0x8000;
+
+const EEPROM_WORDS: usize = 128;
+
+#[derive(Clone, Copy)]
+enum WriteTarget {
+    Word(usize),
+    All,
+}
+
+// Where the EEPROM is in a command, which is clocked in one bit at a time
+#[derive(Clone, Copy)]
+enum EepromState {
+    Idle, // Waiting for a start bit
+    Command {
+        bits: u8,
+        value: u16,
+    },
+    Reading {
+        addr: usize,
+        bit: u8,
+    },
+    Data {
+        target: WriteTarget,
+        bits: u8,
+        value: u16,
+    },
+    Done, // Until chip select is dropped
+}
+
+/*
+    93LC56 serial EEPROM, organised as 128 16 bit words. Commands are a start bit, a 2 bit opcode
+    and a 7 bit address (plus a don't care bit), shifted in MSB first on rising clock edges.
+    Opcode 0 uses the top two address bits to pick EWDS, WRAL, ERAL or EWEN.
+*/
+struct Eeprom {
+    words: [u16; EEPROM_WORDS],
+    write_enabled: bool,
+    chip_select: bool,
+    clock: bool,
+    data_in: bool,
+    data_out: bool,
+    state: EepromState,
+}
+
+impl Eeprom {
+    fn new() -> Self {
+        Self {
+            words: [0xFFFF; EEPROM_WORDS],
+            write_enabled: false,
+            chip_select: false,
+            clock: false,
+            data_in: false,
+            data_out: true,
+            state: EepromState::Idle,
+        }
+    }
+
+    fn read(&self) -> u8 {
+        ((self.chip_select as u8) << 7)
+            | ((self.clock as u8) << 6)
+            | ((self.data_in as u8) << 1)
+            | self.data_out as u8
+    }
+
+    fn write(&mut self, data: u8) {
+        let chip_select = data & 0x80 != 0;
+        let clock = data & 0x40 != 0;
+        let rising_edge = clock && !self.clock;
+
+        self.chip_select = chip_select;
+        self.clock = clock;
+        self.data_in = data & 0x02 != 0;
+
+        if !chip_select {
+            self.state = EepromState::Idle;
+            self.data_out = true;
+            return;
+        }
+
+        if rising_edge {
+            self.clock_bit();
+        }
+    }
+
+    fn clock_bit(&mut self) {
+        let bit = self.data_in as u16;
+
+        self.state = match self.state {
+            EepromState::Idle if self.data_in => EepromState::Command { bits: 0, value: 0 },
+            EepromState::Idle => EepromState::Idle,
+            EepromState::Command { bits, value } => {
+                let value = (value << 1) | bit;
+
+                if bits + 1 < 10 {
+                    EepromState::Command {
+                        bits: bits + 1,
+                        value,
+                    }
+                } else {
+                    self.command(value)
+                }
+            }
+            EepromState::Reading { addr, bit } => {
+                self.data_out = self.words[addr] & (0x8000 >> bit) != 0;
+
+                // Reads carry on into the next word for as long as the clock keeps running
+                match bit {
+                    15 => EepromState::Reading {
+                        addr: (addr + 1) % EEPROM_WORDS,
+                        bit: 0,
+                    },
+                    _ => EepromState::Reading { addr, bit: bit + 1 },
+                }
+            }
+            EepromState::Data {
+                target,
+                bits,
+                value,
+            } => {
+                let value = (value << 1) | bit;
+
+                if bits + 1 < 16 {
+                    EepromState::Data {
+                        target,
+                        bits: bits + 1,
+                        value,
+                    }
+                } else {
+                    self.finish_write(target, value)
+                }
+            }
+            EepromState::Done => EepromState::Done,
+        };
+    }
+
+    // Writes complete instantly, so DO reports ready straight away
+    fn finish_write(&mut self, target: WriteTarget, value: u16) -> EepromState {
+        if self.write_enabled {
+            match target {
+                WriteTarget::Word(addr) => self.words[addr] = value,
+                WriteTarget::All => self.words = [value; EEPROM_WORDS],
+            }
+        }
+
+        self.data_out = true;
+        EepromState::Done
+    }
+
+    fn command(&mut self, command: u16) -> EepromState {
+        let addr = (command & 0x7F) as usize;
+
+        match (command >> 8, (command >> 6) & 0x03) {
+            (0b10, _) => {
+                self.data_out = false; // Dummy bit before the data
+                return EepromState::Reading { addr, bit: 0 };
+            }
+            (0b01, _) => {
+                return EepromState::Data {
+                    target: WriteTarget::Word(addr),
+                    bits: 0,
+                    value: 0,
+                };
+            }
+            (0b00, 0b01) => {
+                return EepromState::Data {
+                    target: WriteTarget::All,
+                    bits: 0,
+                    value: 0,
+                };
+            }
+            (0b11, _) if self.write_enabled => self.words[addr] = 0xFFFF,
+            (0b00, 0b10) if self.write_enabled => self.words = [0xFFFF; EEPROM_WORDS],
+            (0b00, 0b11) => self.write_enabled = true,
+            (0b00, 0b00) => self.write_enabled = false,
+            _ => {}
+        }
+
+        self.data_out = true;
+        EepromState::Done
+    }
+}
+
+/*
+    MBC7 replaces cartridge RAM with a 2 axis accelerometer and a serial EEPROM.
+    Both are only reachable once RAM is enabled through both enable registers, and their
+    registers are selected by bits 4-7 of the address in 0xA000 -> 0xAFFF.
+*/
+pub struct Mbc7 {
+    rom: Rom,
+    eeprom: Eeprom,
+    ram_enabled: [bool; 2],
+    rom_bank: u8,
+    tilt: (u16, u16),    // Live accelerometer reading
+    latched: (u16, u16), // What the game reads
+    latch_ready: bool,   // Latched values were erased, so the next latch takes effect
+}
+
+impl Mbc7 {
+    pub fn new(rom: Rom) -> Self {
+        let level = TILT_CENTER as u16;
+
+        Self {
+            rom,
+            eeprom: Eeprom::new(),
+            ram_enabled: [false; 2],
+            rom_bank: 1,
+            tilt: (level, level),
+            latched: (TILT_ERASED, TILT_ERASED),
+            latch_ready: false,
+        }
+    }
+
+    fn registers_enabled(&self) -> bool {
+        self.ram_enabled == [true, true]
+    }
+}
+
+impl Mapper for Mbc7 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.read(0, addr),
+            _ => self.rom.read(self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled[0] = data == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = data & 0x7F,
+            0x4000..=0x5FFF => self.ram_enabled[1] = data == 0x40,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.registers_enabled() || addr >= 0xB000 {
+            return 0xFF;
+        }
+
+        let (x, y) = self.latched;
+
+        match (addr >> 4) & 0x0F {
+            0x2 => x as u8,
+            0x3 => (x >> 8) as u8,
+            0x4 => y as u8,
+            0x5 => (y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, data: u8) {
+        if !self.registers_enabled() || addr >= 0xB000 {
+            return;
+        }
+
+        match (addr >> 4) & 0x0F {
+            0x0 if data == 0x55 => {
+                self.latched = (TILT_ERASED, TILT_ERASED);
+                self.latch_ready = true;
+            }
+            0x1 if data == 0xAA && self.latch_ready => {
+                self.latched = self.tilt;
+                self.latch_ready = false;
+            }
+            0x8 => self.eeprom.write(data),
+            _ => {}
+        }
+    }
+
+    fn bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ => 0,
+        }
+    }
+
+    fn set_tilt(&mut self, x: f32, y: f32) {
+        let axis = |g: f32| (TILT_CENTER + g * TILT_PER_G).clamp(0.0, u16::MAX as f32) as u16;
+
+        self.tilt = (axis(x), axis(y));
+    }
+
+    // The EEPROM's words, low byte first
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(
+            self.eeprom
+                .words
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .collect(),
+        )
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        for (word, bytes) in self.eeprom.words.iter_mut().zip(data.chunks_exact(2)) {
+            *word = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CS: u8 = 0x80;
+    const CLK: u8 = 0x40;
+    const DI: u8 = 0x02;
+
+    fn make_mbc7() -> Mbc7 {
+        let mut mbc = Mbc7::new(Rom::new(vec![0; 0x8000]));
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x40);
+        mbc
+    }
+
+    // Clocks bits into the EEPROM MSB first, returning what DO read after each rising edge
+    fn clock_bits(mbc: &mut Mbc7, value: u32, count: u32) -> u32 {
+        let mut out = 0;
+
+        for i in (0..count).rev() {
+            let di = if value & (1 << i) != 0 { DI } else { 0 };
+            mbc.write_ram(0xA080, CS | di);
+            mbc.write_ram(0xA080, CS | CLK | di);
+            out = (out << 1) | (mbc.read_ram(0xA080) & 1) as u32;
+        }
+
+        out
+    }
+
+    // Start bit, opcode and address
+    fn command(opcode: u32, addr: u32) -> u32 {
+        0x400 | (opcode << 8) | addr
+    }
+
+    fn end_command(mbc: &mut Mbc7) {
+        mbc.write_ram(0xA080, 0x00);
+    }
+
+    #[test]
+    fn test_accelerometer_latch() {
+        let mut mbc = make_mbc7();
+        mbc.set_tilt(1.0, -0.5);
+
+        // Latching without erasing first does nothing
+        mbc.write_ram(0xA010, 0xAA);
+        assert_eq!(mbc.read_ram(0xA020), 0x00);
+        assert_eq!(mbc.read_ram(0xA030), 0x80);
+
+        mbc.write_ram(0xA000, 0x55);
+        mbc.write_ram(0xA010, 0xAA);
+
+        let x = u16::from_le_bytes([mbc.read_ram(0xA020), mbc.read_ram(0xA030)]);
+        let y = u16::from_le_bytes([mbc.read_ram(0xA040), mbc.read_ram(0xA050)]);
+        assert_eq!(x, 0x81D0 + 0x70);
+        assert_eq!(y, 0x81D0 - 0x38);
+
+        // Registers are mirrored through 0xAxx0
+        assert_eq!(mbc.read_ram(0xA520), mbc.read_ram(0xA020));
+    }
+
+    #[test]
+    fn test_eeprom_write_and_read() {
+        let mut mbc = make_mbc7();
+
+        clock_bits(&mut mbc, command(0b00, 0xC0), 11); // EWEN
+        end_command(&mut mbc);
+
+        clock_bits(&mut mbc, command(0b01, 5), 11); // WRITE word 5
+        clock_bits(&mut mbc, 0xBEEF, 16);
+        end_command(&mut mbc);
+
+        clock_bits(&mut mbc, command(0b10, 5), 11); // READ word 5
+        assert_eq!(mbc.read_ram(0xA080) & 1, 0); // Dummy bit
+        assert_eq!(clock_bits(&mut mbc, 0, 16), 0xBEEF);
+        assert_eq!(clock_bits(&mut mbc, 0, 16), 0xFFFF); // Word 6, still erased
+        end_command(&mut mbc);
+
+        let save = mbc.save_data().unwrap();
+        assert_eq!(save.len(), 256);
+        assert_eq!(&save[10..12], &[0xEF, 0xBE]);
+    }
+
+    #[test]
+    fn test_eeprom_writes_need_enable() {
+        let mut mbc = make_mbc7();
+
+        clock_bits(&mut mbc, command(0b01, 0), 11); // WRITE word 0
+        clock_bits(&mut mbc, 0x1234, 16);
+        end_command(&mut mbc);
+
+        assert_eq!(&mbc.save_data().unwrap()[0..2], &[0xFF, 0xFF]);
+    }
+}
@@ -1,17 +1,292 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+mod camera;
 mod header;
+mod huc1;
+mod huc3;
+mod infrared;
+mod mapper;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc7;
+mod rtc;
+
+use camera::Camera;
+pub use camera::{CAMERA_HEIGHT, CAMERA_WIDTH, CameraFrame, ImageSource, StaticImage, TestPattern};
+pub use header::{CartridgeHeader, CgbSupport, Destination, HeaderError, MapperKind};
+use header::{HEADER_END, LOGO_ADDR};
+pub(crate) use header::{NINTENDO_LOGO, global_checksum, header_checksum};
+use huc1::HuC1;
+use huc3::HuC3;
+pub use infrared::{InfraredPort, NoInfrared};
+use mapper::{Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE, Ram, Rom, RomOnly};
+use mbc1::Mbc1;
+use mbc2::Mbc2;
+use mbc3::Mbc3;
+use mbc5::Mbc5;
+use mbc7::Mbc7;
+pub use rtc::RtcClock;
+
+// Things cartridge hardware does outside of the console, for frontends to act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CartridgeEvent {
+    Rumble(bool), // Motor turned on or off
+    Tone,         // HuC3 speaker played its tone
+}
+
+// Why a ROM couldn't be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    NotFound(PathBuf),
+    Io(io::Error),
+    Truncated { expected: usize, actual: usize }, // Shorter than its header, or than the size it declares
+    UnsupportedMapper(u8),                        // Cartridge type byte
+    BadHeader(HeaderError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotFound(path) => write!(f, "{} doesn't exist", path.display()),
+            LoadError::Io(error) => write!(f, "couldn't read the ROM: {}", error),
+            LoadError::Truncated { expected, actual } => write!(
+                f,
+                "ROM is truncated, expected {} bytes but got {}",
+                expected, actual
+            ),
+            LoadError::UnsupportedMapper(cartridge_type) => {
+                write!(f, "cartridge type ${:02X} isn't supported", cartridge_type)
+            }
+            LoadError::BadHeader(error) => write!(f, "bad cartridge header: {}", error),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            LoadError::BadHeader(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 pub(super) struct Cartridge {
-    size: u64,
-    pub rom: Vec<u8>,
-    ram: [u8; 8 * 1024],
+    header: CartridgeHeader,
+    mapper: Box<dyn Mapper>,
 }
 
 impl Cartridge {
-    pub fn new(size: u64, rom: Vec<u8>) -> Self {
-        Self {
-            size,
-            rom,
-            ram: [0; 8 * 1024],
+    /*
+        Only accepts ROMs the boot ROM would start (matching logo and header checksum),
+        with a mapper that's implemented and all the banks the header declares.
+    */
+    pub fn load(rom: Vec<u8>) -> Result<Self, LoadError> {
+        let header = CartridgeHeader::parse(&rom).map_err(|error| match error {
+            HeaderError::Truncated(actual) => LoadError::Truncated {
+                expected: HEADER_END,
+                actual,
+            },
+            error => LoadError::BadHeader(error),
+        })?;
+
+        header.check_logo().map_err(LoadError::BadHeader)?;
+        header
+            .check_header_checksum()
+            .map_err(LoadError::BadHeader)?;
+
+        if header.mapper().is_none() {
+            return Err(LoadError::UnsupportedMapper(header.cartridge_type));
+        }
+
+        if let Some(expected) = header.rom_size
+            && rom.len() < expected
+        {
+            return Err(LoadError::Truncated {
+                expected,
+                actual: rom.len(),
+            });
         }
+
+        Ok(Self::with_header(rom, header))
+    }
+
+    /*
+        Loads anything, for tests without a proper header.
+        Types without a mapper implementation are treated as ROM only, as are ROMs
+        too short to have a header.
+    */
+    #[cfg(test)]
+    pub fn new(rom: Vec<u8>) -> Self {
+        let header = CartridgeHeader::parse(&rom).unwrap_or_default();
+        Self::with_header(rom, header)
+    }
+
+    // Picks the mapper and sizes RAM from the header
+    fn with_header(rom: Vec<u8>, header: CartridgeHeader) -> Self {
+        let ram = Ram::new(header.ram_size);
+        let mapper_kind = header.mapper().unwrap_or(MapperKind::RomOnly);
+
+        let mapper: Box<dyn Mapper> = match mapper_kind {
+            MapperKind::RomOnly => Box::new(RomOnly::new(Rom::new(rom), ram)),
+            MapperKind::Mbc1 => {
+                let multicart = is_mbc1_multicart(&rom);
+                Box::new(Mbc1::new(Rom::new(rom), ram, multicart))
+            }
+            MapperKind::Mbc2 => Box::new(Mbc2::new(Rom::new(rom), header.has_battery())),
+            MapperKind::Mbc3 => {
+                // MBC30 isn't marked in the header, only carts using its extra bank bits need it
+                let mbc30 = rom.len() > 0x200000 || header.ram_size > 4 * RAM_BANK_SIZE;
+                Box::new(Mbc3::new(Rom::new(rom), ram, header.has_rtc(), mbc30))
+            }
+            MapperKind::Mbc5 => Box::new(Mbc5::new(Rom::new(rom), ram, header.has_rumble())),
+            MapperKind::Mbc7 => Box::new(Mbc7::new(Rom::new(rom))),
+            MapperKind::PocketCamera => Box::new(Camera::new(Rom::new(rom), ram)),
+            MapperKind::HuC1 => Box::new(HuC1::new(Rom::new(rom), ram)),
+            MapperKind::HuC3 => Box::new(HuC3::new(Rom::new(rom), ram)),
+        };
+
+        Self { header, mapper }
+    }
+
+    pub fn header(&self) -> &CartridgeHeader {
+        &self.header
+    }
+
+    pub fn read_rom(&self, addr: u16) -> u8 {
+        self.mapper.read_rom(addr)
+    }
+
+    pub fn write_rom(&mut self, addr: u16, data: u8) {
+        self.mapper.write_rom(addr, data);
+    }
+
+    pub fn read_ram(&self, addr: u16) -> u8 {
+        self.mapper.read_ram(addr)
+    }
+
+    pub fn write_ram(&mut self, addr: u16, data: u8) {
+        self.mapper.write_ram(addr, data);
+    }
+
+    pub fn bank(&self, addr: u16) -> u16 {
+        self.mapper.bank(addr)
+    }
+
+    pub fn tick(&mut self, t_cycles: u8) {
+        self.mapper.tick(t_cycles);
+    }
+
+    pub fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.mapper.set_infrared_port(port);
+    }
+
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.mapper.set_image_source(source);
+    }
+
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.mapper.set_tilt(x, y);
+    }
+
+    // Only carts with a battery keep anything while switched off
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        if !self.header.has_battery() {
+            return None;
+        }
+
+        self.mapper.save_data().filter(|data| !data.is_empty())
+    }
+
+    pub fn clock_save_size(&self) -> usize {
+        self.mapper.clock_save_size()
+    }
+
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.mapper.load_save_data(data);
+    }
+
+    pub fn drain_events(&mut self, events: &mut Vec<CartridgeEvent>) {
+        self.mapper.drain_events(events);
+    }
+
+    // Has no effect on cartridges without a clock
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.mapper.set_rtc_clock(clock);
+    }
+}
+
+/*
+    MBC1M multicarts report themselves as plain MBC1, but are always 1 MiB
+    and have a second game, with its own copy of the logo, starting at bank 0x10.
+*/
+fn is_mbc1_multicart(rom: &[u8]) -> bool {
+    const SECOND_GAME: usize = 0x10 * ROM_BANK_SIZE;
+
+    let logo = |start: usize| rom.get(start + LOGO_ADDR..start + LOGO_ADDR + NINTENDO_LOGO.len());
+
+    rom.len() == 0x100000 && logo(0).is_some() && logo(0) == logo(SECOND_GAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 32 KiB MBC1 ROM with a valid header
+    fn make_rom() -> Vec<u8> {
+        let mut rom = vec![0; 2 * ROM_BANK_SIZE];
+        rom[LOGO_ADDR..LOGO_ADDR + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+        rom[0x147] = 0x01;
+        rom[0x14D] = header_checksum(&rom);
+        rom
+    }
+
+    #[test]
+    fn test_load_accepts_valid_rom() {
+        assert!(Cartridge::load(make_rom()).is_ok());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let result = Cartridge::load(vec![0; 0x100]);
+        assert!(matches!(
+            result,
+            Err(LoadError::Truncated {
+                expected: 0x150,
+                actual: 0x100
+            })
+        ));
+
+        let mut rom = make_rom();
+        rom.truncate(ROM_BANK_SIZE);
+        assert!(matches!(
+            Cartridge::load(rom),
+            Err(LoadError::Truncated { .. })
+        ));
+
+        let mut rom = make_rom();
+        rom[0x147] = 0x20; // MBC6
+        rom[0x14D] = header_checksum(&rom);
+        assert!(matches!(
+            Cartridge::load(rom),
+            Err(LoadError::UnsupportedMapper(0x20))
+        ));
+
+        let mut rom = make_rom();
+        rom[0x14D] ^= 0xFF;
+        assert!(matches!(
+            Cartridge::load(rom),
+            Err(LoadError::BadHeader(HeaderError::HeaderChecksum { .. }))
+        ));
+
+        let mut rom = make_rom();
+        rom[LOGO_ADDR] = 0;
+        assert!(matches!(
+            Cartridge::load(rom),
+            Err(LoadError::BadHeader(HeaderError::LogoMismatch))
+        ));
     }
 }
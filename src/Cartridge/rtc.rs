@@ -0,0 +1,515 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// T-cycles of the 4 MiHz clock per second, the cartridge's 32 KiHz crystal divides evenly into it
+const CYCLES_PER_SECOND: u32 = 4 * 1024 * 1024;
+
+// Live and latched registers as 32 bit words, then a 64 bit timestamp
+pub const RTC_SAVE_SIZE: usize = 48;
+// Older saves with a 32 bit timestamp
+const RTC_SAVE_SIZE_SHORT: usize = 44;
+
+const HALT_BIT: u8 = 0x40;
+const CARRY_BIT: u8 = 0x80;
+
+// What moves the cartridge clock forward
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtcClock {
+    // Emulated cycles, so the clock stays in step with the game when running fast or paused
+    Emulated,
+    // Wall clock time, like a real cartridge that keeps counting while the console is off
+    Host,
+}
+
+// Counts whole seconds from whichever clock is selected, for the cartridge clocks built on it
+pub struct ClockSource {
+    clock: RtcClock,
+    cycles: u32, // T-cycles towards the next second
+    last_sync: SystemTime,
+    // When a loaded save was written, until the time since then has been counted on the host clock
+    saved_at: Option<u64>,
+}
+
+impl ClockSource {
+    pub fn new() -> Self {
+        Self {
+            clock: RtcClock::Emulated,
+            cycles: 0,
+            last_sync: SystemTime::now(),
+            saved_at: None,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: RtcClock) {
+        self.clock = clock;
+        self.last_sync = SystemTime::now();
+    }
+
+    // Starts the current second over
+    pub fn reset_divider(&mut self) {
+        self.cycles = 0;
+    }
+
+    // Returns true when an emulated second has passed
+    pub fn tick(&mut self, t_cycles: u8) -> bool {
+        if self.clock != RtcClock::Emulated {
+            return false;
+        }
+
+        self.cycles += t_cycles as u32;
+        if self.cycles < CYCLES_PER_SECOND {
+            return false;
+        }
+
+        self.cycles -= CYCLES_PER_SECOND;
+        true
+    }
+
+    /*
+        Remembers when a loaded save was written. The next host_seconds counts from then instead,
+        so the time still counts if the host clock is only selected after loading.
+    */
+    pub fn restore(&mut self, timestamp: u64) {
+        self.saved_at = Some(timestamp);
+    }
+
+    /*
+        When the counters were last brought up to date, to save alongside them. Host time is only
+        counted when synced, so saving the current time would lose the seconds since then.
+    */
+    pub fn timestamp(&self) -> u64 {
+        match self.clock {
+            RtcClock::Host => self.saved_at.unwrap_or_else(|| {
+                self.last_sync
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs())
+            }),
+            RtcClock::Emulated => unix_time(),
+        }
+    }
+
+    // Pretends the last sync happened earlier, as if host time had passed since
+    #[cfg(test)]
+    pub fn rewind(&mut self, seconds: u64) {
+        self.last_sync -= Duration::from_secs(seconds);
+    }
+
+    // Whole wall clock seconds since the last call, keeping the fraction of a second for next time
+    pub fn host_seconds(&mut self) -> u64 {
+        if self.clock != RtcClock::Host {
+            return 0;
+        }
+
+        if let Some(timestamp) = self.saved_at.take() {
+            self.last_sync = UNIX_EPOCH + Duration::from_secs(timestamp);
+        }
+
+        let now = SystemTime::now();
+        let Ok(elapsed) = now.duration_since(self.last_sync) else {
+            // The host clock went backwards, start counting again from here
+            self.last_sync = now;
+            return 0;
+        };
+
+        let seconds = elapsed.as_secs();
+        self.last_sync += Duration::from_secs(seconds);
+
+        seconds
+    }
+}
+
+/*
+    The MBC3 real-time clock. Games read it through a latch, which copies the live counters
+    so they don't change mid-read. Each counter only carries when it reaches its limit exactly,
+    so out of range values written by a game count up to the register's maximum and wrap to 0.
+*/
+pub struct Rtc {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16, // 9 bits
+    halted: bool,
+    carry: bool, // Day counter overflowed, stays set until the game clears it
+    latched: [u8; 5],
+    source: ClockSource,
+}
+
+impl Rtc {
+    pub fn new() -> Self {
+        Self {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            carry: false,
+            latched: [0; 5],
+            source: ClockSource::new(),
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: RtcClock) {
+        self.source.set_clock(clock);
+    }
+
+    pub fn tick(&mut self, t_cycles: u8) {
+        if !self.halted && self.source.tick(t_cycles) {
+            self.advance(1);
+        }
+    }
+
+    // Catches up with the wall clock when following the host
+    fn sync(&mut self) {
+        let seconds = self.source.host_seconds();
+
+        if !self.halted {
+            self.advance(seconds);
+        }
+    }
+
+    // Worked out per counter rather than a second at a time, since a save can be years old
+    fn advance(&mut self, seconds: u64) {
+        let minutes = count_up(&mut self.seconds, 60, 0x3F, seconds);
+        let hours = count_up(&mut self.minutes, 60, 0x3F, minutes);
+        let days = count_up(&mut self.hours, 24, 0x1F, hours);
+
+        let days = self.days as u64 + days;
+        self.days = (days & 0x1FF) as u16;
+        if days > 0x1FF {
+            self.carry = true;
+        }
+    }
+
+    pub fn latch(&mut self) {
+        self.sync();
+
+        self.latched = self.registers();
+    }
+
+    fn day_high(&self) -> u8 {
+        ((self.carry as u8) << 7) | ((self.halted as u8) << 6) | (self.days >> 8) as u8
+    }
+
+    fn registers(&self) -> [u8; 5] {
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days as u8,
+            self.day_high(),
+        ]
+    }
+
+    /*
+        The layout VBA-M, BGB and SameBoy append to MBC3 saves, so clocks carry over between them.
+        The timestamp lets the clock catch up on the time the emulator was closed, when following the host.
+    */
+    pub fn save(&self) -> Vec<u8> {
+        let words = self.registers().into_iter().chain(self.latched);
+
+        words
+            .flat_map(|register| (register as u32).to_le_bytes())
+            .chain(self.source.timestamp().to_le_bytes())
+            .collect()
+    }
+
+    pub fn load(&mut self, data: &[u8]) {
+        let timestamp = match data.len() {
+            RTC_SAVE_SIZE => u64::from_le_bytes(data[40..48].try_into().unwrap()),
+            RTC_SAVE_SIZE_SHORT => u32::from_le_bytes(data[40..44].try_into().unwrap()) as u64,
+            _ => return,
+        };
+
+        let word = |i: usize| data[i * 4];
+        self.seconds = word(0) & 0x3F;
+        self.minutes = word(1) & 0x3F;
+        self.hours = word(2) & 0x1F;
+        self.days = ((word(4) as u16 & 0x01) << 8) | word(3) as u16;
+        self.halted = word(4) & HALT_BIT != 0;
+        self.carry = word(4) & CARRY_BIT != 0;
+        self.latched = std::array::from_fn(|i| word(5 + i));
+
+        self.source.restore(timestamp);
+        self.sync();
+    }
+
+    // Registers 0x08 -> 0x0C as selected through the RAM bank register
+    pub fn read(&self, register: u8) -> u8 {
+        self.latched
+            .get(register.wrapping_sub(0x08) as usize)
+            .copied()
+            .unwrap_or(0xFF)
+    }
+
+    // Writes go to the live counters, and take effect in the latched copy too
+    pub fn write(&mut self, register: u8, data: u8) {
+        self.sync();
+
+        match register {
+            0x08 => {
+                self.seconds = data & 0x3F;
+                self.source.reset_divider(); // Writing the seconds resets the divider
+            }
+            0x09 => self.minutes = data & 0x3F,
+            0x0A => self.hours = data & 0x1F,
+            0x0B => self.days = (self.days & 0x100) | data as u16,
+            0x0C => {
+                self.days = (self.days & 0xFF) | ((data as u16 & 0x01) << 8);
+                self.halted = data & HALT_BIT != 0;
+                self.carry = data & CARRY_BIT != 0;
+            }
+            _ => return,
+        }
+
+        let index = (register - 0x08) as usize;
+        self.latched[index] = match register {
+            0x08 => self.seconds,
+            0x09 => self.minutes,
+            0x0A => self.hours,
+            0x0B => self.days as u8,
+            _ => self.day_high(),
+        };
+    }
+}
+
+/*
+    Adds `amount` to a counter that carries into the next one when it reaches `limit`,
+    returning the number of carries. Out of range values count up to `mask` and wrap to 0 first.
+*/
+fn count_up(value: &mut u8, limit: u8, mask: u8, amount: u64) -> u64 {
+    let mut amount = amount;
+
+    if *value >= limit {
+        let until_wrap = (mask - *value) as u64 + 1;
+        if amount < until_wrap {
+            *value += amount as u8;
+            return 0;
+        }
+
+        amount -= until_wrap;
+        *value = 0;
+    }
+
+    let total = *value as u64 + amount;
+    *value = (total % limit as u64) as u8;
+
+    total / limit as u64
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_seconds(rtc: &mut Rtc, seconds: u32) {
+        for _ in 0..seconds * CYCLES_PER_SECOND / 4 {
+            rtc.tick(4);
+        }
+    }
+
+    #[test]
+    fn test_counts_emulated_time_through_latch() {
+        let mut rtc = Rtc::new();
+        rtc.write(0x08, 59);
+        rtc.write(0x09, 59);
+        rtc.write(0x0A, 23);
+
+        run_seconds(&mut rtc, 1);
+        assert_eq!(rtc.read(0x08), 59); // Not latched yet
+
+        rtc.latch();
+        assert_eq!(
+            [0x08, 0x09, 0x0A, 0x0B].map(|register| rtc.read(register)),
+            [0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_halt_and_day_carry() {
+        let mut rtc = Rtc::new();
+        rtc.write(0x0C, HALT_BIT | 0x01);
+        rtc.write(0x0B, 0xFF);
+        rtc.write(0x0A, 23);
+        rtc.write(0x09, 59);
+        rtc.write(0x08, 59);
+
+        run_seconds(&mut rtc, 1);
+        rtc.latch();
+        assert_eq!(rtc.read(0x08), 59);
+
+        rtc.write(0x0C, 0x01);
+        run_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(rtc.read(0x0B), 0);
+        assert_eq!(rtc.read(0x0C), CARRY_BIT);
+    }
+
+    #[test]
+    fn test_out_of_range_values_wrap_without_carrying() {
+        let mut rtc = Rtc::new();
+        rtc.write(0x08, 63);
+
+        rtc.advance(1);
+        rtc.latch();
+
+        assert_eq!(rtc.read(0x08), 0);
+        assert_eq!(rtc.read(0x09), 0);
+    }
+
+    // One second at a time, the way the counters are wired
+    fn count_second(rtc: &mut Rtc) {
+        rtc.seconds = (rtc.seconds + 1) & 0x3F;
+        if rtc.seconds != 60 {
+            return;
+        }
+        rtc.seconds = 0;
+
+        rtc.minutes = (rtc.minutes + 1) & 0x3F;
+        if rtc.minutes != 60 {
+            return;
+        }
+        rtc.minutes = 0;
+
+        rtc.hours = (rtc.hours + 1) & 0x1F;
+        if rtc.hours != 24 {
+            return;
+        }
+        rtc.hours = 0;
+
+        rtc.days = (rtc.days + 1) & 0x1FF;
+        if rtc.days == 0 {
+            rtc.carry = true;
+        }
+    }
+
+    #[test]
+    fn test_advance_matches_counting_each_second() {
+        let starts = [
+            (0, 0, 0, 0),
+            (59, 59, 23, 0x1FF),
+            (62, 61, 30, 0x1FE),
+            (12, 34, 5, 0x100),
+        ];
+
+        for (seconds, minutes, hours, days) in starts {
+            for elapsed in [0, 1, 3, 59, 61, 3599, 3601, 86_399, 86_401, 200_000] {
+                let mut stepped = Rtc::new();
+                (
+                    stepped.seconds,
+                    stepped.minutes,
+                    stepped.hours,
+                    stepped.days,
+                ) = (seconds, minutes, hours, days);
+                let mut jumped = Rtc::new();
+                (jumped.seconds, jumped.minutes, jumped.hours, jumped.days) =
+                    (seconds, minutes, hours, days);
+
+                for _ in 0..elapsed {
+                    count_second(&mut stepped);
+                }
+                jumped.advance(elapsed);
+
+                assert_eq!(stepped.registers(), jumped.registers(), "{elapsed}s");
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_with_zero_timestamp() {
+        let mut save = Rtc::new().save();
+        save[40..48].copy_from_slice(&0u64.to_le_bytes());
+
+        let mut rtc = Rtc::new();
+        rtc.set_clock(RtcClock::Host);
+        rtc.load(&save);
+        rtc.latch();
+
+        // Decades of days, so the day counter has overflowed
+        assert_eq!(rtc.read(0x0C) & CARRY_BIT, CARRY_BIT);
+    }
+
+    #[test]
+    fn test_save_catches_up_on_host_time() {
+        let mut rtc = Rtc::new();
+        rtc.write(0x0A, 5);
+        rtc.latch();
+
+        let mut save = rtc.save();
+        assert_eq!(save.len(), RTC_SAVE_SIZE);
+        assert_eq!(&save[8..12], &[5, 0, 0, 0]);
+
+        // Saved an hour ago
+        let timestamp = u64::from_le_bytes(save[40..48].try_into().unwrap()) - 3600;
+        save[40..48].copy_from_slice(&timestamp.to_le_bytes());
+
+        let mut restored = Rtc::new();
+        restored.set_clock(RtcClock::Host);
+        restored.load(&save);
+        assert_eq!(restored.read(0x0A), 5); // Latched copy is as saved
+
+        restored.latch();
+        assert_eq!(restored.read(0x0A), 6);
+    }
+
+    #[test]
+    fn test_save_keeps_host_time_since_the_last_latch() {
+        let mut rtc = Rtc::new();
+        rtc.set_clock(RtcClock::Host);
+        rtc.latch();
+
+        // An hour passes without the game latching before the save is written
+        rtc.source.rewind(3600);
+        let save = rtc.save();
+
+        let mut restored = Rtc::new();
+        restored.set_clock(RtcClock::Host);
+        restored.load(&save);
+        restored.latch();
+
+        assert_eq!(restored.read(0x0A), 1);
+    }
+
+    #[test]
+    fn test_time_since_save_is_only_counted_once() {
+        let mut save = Rtc::new().save();
+        let timestamp = u64::from_le_bytes(save[40..48].try_into().unwrap()) - 3600;
+        save[40..48].copy_from_slice(&timestamp.to_le_bytes());
+
+        // The host clock was selected a while before the save was loaded
+        let mut rtc = Rtc::new();
+        rtc.set_clock(RtcClock::Host);
+        rtc.source.rewind(100);
+        rtc.load(&save);
+        rtc.latch();
+
+        assert_eq!([rtc.read(0x09), rtc.read(0x0A)], [0, 1]);
+    }
+
+    #[test]
+    fn test_host_clock_selected_after_load_catches_up() {
+        let mut rtc = Rtc::new();
+        rtc.write(0x0A, 5);
+
+        let mut save = rtc.save();
+        let timestamp = u64::from_le_bytes(save[40..48].try_into().unwrap()) - 3600;
+        save[40..48].copy_from_slice(&timestamp.to_le_bytes());
+
+        let mut restored = Rtc::new();
+        restored.load(&save);
+        restored.latch();
+        assert_eq!(restored.read(0x0A), 5); // Emulated time doesn't see the hour
+
+        restored.set_clock(RtcClock::Host);
+        restored.latch();
+        assert_eq!(restored.read(0x0A), 6);
+
+        // Only counted once
+        restored.latch();
+        assert_eq!(restored.read(0x0A), 6);
+    }
+}
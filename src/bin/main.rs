@@ -1,10 +1,41 @@
 use std::env;
 
-use gb_core::Emulator;
+use gb_core::{Emulator, Model};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let mut emu = Emulator::new(&args[1]);
 
-    emu.execute();
+    if args.len() < 2 {
+        eprintln!("Usage: {} <rom> [dmg0|dmg|mgb|sgb|sgb2|cgb|agb]", args[0]);
+        std::process::exit(1);
+    }
+
+    // Optional second argument picks the console, e.g. `cgb`
+    let model = match args.get(2).map(|name| name.parse::<Model>()) {
+        Some(Ok(model)) => model,
+        Some(Err(why)) => {
+            eprintln!("{why}");
+            std::process::exit(1);
+        }
+        None => Model::Dmg,
+    };
+
+    let mut emu = match Emulator::from_path(&args[1], model) {
+        Ok(emu) => emu,
+        Err(why) => {
+            eprintln!("{why}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(error) = emu.execute() {
+        eprintln!("{error}");
+
+        // exit skips destructors, so the save has to be written out first
+        if let Err(why) = emu.flush_save() {
+            eprintln!("Couldn't write the save file: {why}");
+        }
+
+        std::process::exit(1);
+    }
 }
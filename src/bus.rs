@@ -1,13 +1,92 @@
-use crate::cartridge::Cartridge;
-use std::{fs::File, io::Read, path::Path};
+use crate::cartridge::{
+    Cartridge, CartridgeEvent, CartridgeHeader, CgbSupport, ImageSource, InfraredPort, LoadError,
+    RtcClock,
+};
+use crate::io::{IO_OFFSET, IO_SIZE, IoRegister, stored_registers};
+use crate::joypad::{Button, Joypad};
+use crate::model::Model;
+use crate::timer::{DIV_ADDR, TAC_ADDR, Timer};
+use std::{cell::RefCell, ops::RangeInclusive, rc::Rc};
 
 pub trait Bus {
     fn read(&self, addr: u16) -> u8;
     fn write(&mut self, addr: u16, data: u8);
-    fn request_interrupt(&mut self);
-    fn enable_interrupts(&mut self);
-    fn disable_interrupts(&mut self);
-    fn interrupts_enabled(&self) -> bool;
+    fn request_interrupt(&mut self, interrupt: Interrupt);
+    // Advances every peripheral on the bus by one M-cycle
+    fn tick(&mut self);
+    // Performs a CGB speed switch if one was armed through KEY1, returning whether it happened
+    fn speed_switch(&mut self) -> bool;
+    // Which bank is mapped at addr, so cached code from different banks isn't mixed up
+    fn code_bank(&self, _addr: u16) -> u16 {
+        0
+    }
+}
+
+// Lets a bus be shared with the CPU (e.g. `Rc<RefCell<dyn Bus>>`), at the cost of a borrow per access
+impl<B: Bus + ?Sized> Bus for Rc<RefCell<B>> {
+    fn read(&self, addr: u16) -> u8 {
+        self.borrow().read(addr)
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        self.borrow_mut().write(addr, data)
+    }
+
+    fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.borrow_mut().request_interrupt(interrupt)
+    }
+
+    fn tick(&mut self) {
+        self.borrow_mut().tick()
+    }
+
+    fn speed_switch(&mut self) -> bool {
+        self.borrow_mut().speed_switch()
+    }
+
+    fn code_bank(&self, addr: u16) -> u16 {
+        self.borrow().code_bank(addr)
+    }
+}
+
+pub const P1_ADDR: u16 = 0xFF00;
+pub const IF_ADDR: u16 = 0xFF0F;
+pub const KEY1_ADDR: u16 = 0xFF4D;
+pub const IE_ADDR: u16 = 0xFFFF;
+
+// Bit positions in IE / IF double as dispatch priority (VBlank is serviced first)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank,
+    Stat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    const PRIORITY: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::Stat,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    pub fn mask(self) -> u8 {
+        1 << (self as u8)
+    }
+
+    pub fn vector(self) -> u16 {
+        0x40 + 8 * (self as u16)
+    }
+
+    // Highest priority interrupt set in a combined IE & IF value
+    pub fn highest_priority(pending: u8) -> Option<Interrupt> {
+        Self::PRIORITY
+            .into_iter()
+            .find(|interrupt| pending & interrupt.mask() != 0)
+    }
 }
 
 const VRAM_SIZE: usize = 8 * 1024;
@@ -19,94 +98,352 @@ const WRAM_OFFSET: u16 = 0xC000;
 const OAM_SIZE: usize = 160;
 const OAM_OFFSET: u16 = 0xFE00;
 
+const ECHO_OFFSET: u16 = 0xE000;
+
 const HRAM_SIZE: usize = 127;
 const HRAM_OFFSET: u16 = 0xFF80;
 
 pub struct SystemBus {
     cartridge: Cartridge,
-    vram: [u8; 8 * 1024],     // 0x8000 -> 0x9FFF
-    wram: [u8; 8 * 1024],     // 0xC000 -> 0xDFFF
-    oam: [u8; 160],           // 0xFE00 -> FE9F
-    hram: [u8; 127],          // 0xFF80 -> 0xFFFE
-    interrupts_enabled: bool, // 0xFFFF
+    timer: Timer,
+    joypad: Joypad,
+    vram: [u8; 8 * 1024],          // 0x8000 -> 0x9FFF
+    wram: [u8; 8 * 1024],          // 0xC000 -> 0xDFFF
+    oam: [u8; 160],                // 0xFE00 -> FE9F
+    io_map: [IoRegister; IO_SIZE], // 0xFF00 -> 0xFF7F, what handles each IO register
+    io: [u8; IO_SIZE],             // Backs IO registers without their own hardware yet
+    hram: [u8; 127],               // 0xFF80 -> 0xFFFE
+    interrupt_flag: u8,            // 0xFF0F
+    interrupt_enable: u8,          // 0xFFFF
+    model: Model,
+    cgb_mode: bool,
+    double_speed: bool,       // KEY1 bit 7
+    speed_switch_armed: bool, // KEY1 bit 0
 }
 
-pub fn load_cartridge(name: &str) -> Cartridge {
-    let path = Path::new(name);
-
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("Couldn't read path {}", why),
-        Ok(file) => file,
-    };
-
-    let size = file.metadata().unwrap().len();
-    let mut buffer = Vec::<u8>::with_capacity(size as usize);
+impl SystemBus {
+    pub fn load(rom: Vec<u8>, model: Model) -> Result<Self, LoadError> {
+        Ok(Self::with_cartridge(Cartridge::load(rom)?, model))
+    }
 
-    match file.read_to_end(&mut buffer) {
-        Err(why) => panic!("Error reading cartridge data {}", why),
-        Ok(_) => Cartridge::new(size, buffer),
+    // Accepts ROMs without a valid header, for tests
+    #[cfg(test)]
+    pub fn from_rom(rom: Vec<u8>, model: Model) -> Self {
+        Self::with_cartridge(Cartridge::new(rom), model)
     }
-}
 
-impl SystemBus {
-    pub fn new(cartridge_file: &str) -> Self {
-        Self {
-            cartridge: load_cartridge(cartridge_file),
+    fn with_cartridge(cartridge: Cartridge, model: Model) -> Self {
+        // CGB hardware falls back to DMG compatibility mode for cartridges without the CGB flag
+        let cgb_mode = model.is_cgb() && cartridge.header().cgb_support != CgbSupport::None;
+
+        let mut bus = Self {
+            cartridge,
+            timer: Timer::new(),
+            joypad: Joypad::new(),
             vram: [0; VRAM_SIZE],
             wram: [0; WRAM_SIZE],
             oam: [0; OAM_SIZE],
+            io_map: stored_registers(model),
+            io: [0; IO_SIZE],
             hram: [0; HRAM_SIZE],
-            interrupts_enabled: false,
+            interrupt_flag: 0,
+            interrupt_enable: 0,
+            model,
+            cgb_mode,
+            double_speed: false,
+            speed_switch_armed: false,
+        };
+
+        bus.map_peripherals();
+        bus.skip_boot_rom(model);
+
+        bus
+    }
+
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.cartridge.set_rtc_clock(clock);
+    }
+
+    pub fn cartridge_header(&self) -> &CartridgeHeader {
+        self.cartridge.header()
+    }
+
+    pub fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.cartridge.set_infrared_port(port);
+    }
+
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.cartridge.set_image_source(source);
+    }
+
+    pub fn press_button(&mut self, button: Button) {
+        if self.joypad.press(button) {
+            self.request_interrupt(Interrupt::Joypad);
+        }
+    }
+
+    pub fn release_button(&mut self, button: Button) {
+        self.joypad.release(button);
+    }
+
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.cartridge.set_tilt(x, y);
+    }
+
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        self.cartridge.save_data()
+    }
+
+    pub fn clock_save_size(&self) -> usize {
+        self.cartridge.clock_save_size()
+    }
+
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.cartridge.load_save_data(data);
+    }
+
+    pub fn take_cartridge_events(&mut self) -> Vec<CartridgeEvent> {
+        let mut events = Vec::new();
+        self.cartridge.drain_events(&mut events);
+
+        events
+    }
+
+    // Points every register in the range at the peripheral that owns it
+    pub(crate) fn map_io(&mut self, addrs: RangeInclusive<u16>, register: IoRegister) {
+        for addr in addrs {
+            self.io_map[(addr - IO_OFFSET) as usize] = register;
+        }
+    }
+
+    fn map_peripherals(&mut self) {
+        self.map_io(
+            P1_ADDR..=P1_ADDR,
+            IoRegister::Device {
+                read: |bus, _| bus.joypad.read(),
+                write: |bus, _, data| {
+                    // Selecting a group with a button held pulls its line low too
+                    if bus.joypad.write(data) {
+                        bus.request_interrupt(Interrupt::Joypad);
+                    }
+                },
+            },
+        );
+
+        self.map_io(
+            DIV_ADDR..=TAC_ADDR,
+            IoRegister::Device {
+                read: |bus, addr| bus.timer.read(addr),
+                write: |bus, addr, data| bus.timer.write(addr, data),
+            },
+        );
+
+        self.map_io(
+            IF_ADDR..=IF_ADDR,
+            IoRegister::Device {
+                read: |bus, _| bus.interrupt_flag | 0xE0, // Upper 3 bits are unused and read as 1
+                write: |bus, _, data| bus.interrupt_flag = data & 0x1F,
+            },
+        );
+
+        if self.cgb_mode {
+            self.map_io(
+                KEY1_ADDR..=KEY1_ADDR,
+                IoRegister::Device {
+                    read: |bus, _| {
+                        ((bus.double_speed as u8) << 7) | 0x7E | bus.speed_switch_armed as u8
+                    },
+                    write: |bus, _, data| bus.speed_switch_armed = data & 1 != 0,
+                },
+            );
+        }
+    }
+
+    fn read_io(&self, addr: u16) -> u8 {
+        match self.io_map[(addr - IO_OFFSET) as usize] {
+            IoRegister::Unmapped => 0xFF,
+            IoRegister::Stored { unused } => self.io[(addr - IO_OFFSET) as usize] | unused,
+            IoRegister::Device { read, .. } => read(self, addr),
+        }
+    }
+
+    fn write_io(&mut self, addr: u16, data: u8) {
+        match self.io_map[(addr - IO_OFFSET) as usize] {
+            IoRegister::Unmapped => {}
+            IoRegister::Stored { .. } => self.io[(addr - IO_OFFSET) as usize] = data,
+            IoRegister::Device { write, .. } => write(self, addr, data),
+        }
+    }
+
+    /*
+        0xFEA0 -> 0xFEFF isn't backed by anything. Monochrome models read 0,
+        while later CGB revisions repeat the upper nibble of the address' low byte.
+    */
+    fn read_unusable(&self, addr: u16) -> u8 {
+        if self.model.is_cgb() {
+            let nibble = (addr as u8) >> 4;
+            (nibble << 4) | nibble
+        } else {
+            0x00
         }
     }
+
+    // Leaves the IO registers as the model's boot ROM would have
+    fn skip_boot_rom(&mut self, model: Model) {
+        for (addr, value) in model.io_registers() {
+            self.write(addr, value);
+        }
+
+        self.timer.set_counter(model.div_counter());
+    }
 }
 
 impl Bus for SystemBus {
     fn read(&self, addr: u16) -> u8 {
-        // TODO: Implement memory mapping
         match addr {
-            0x0000..=0x7FFF => self.cartridge.rom[addr as usize],
+            0x0000..=0x7FFF => self.cartridge.read_rom(addr),
             0x8000..=0x9FFF => self.vram[(addr - VRAM_OFFSET) as usize],
+            0xA000..=0xBFFF => self.cartridge.read_ram(addr),
             0xC000..=0xDFFF => self.wram[(addr - WRAM_OFFSET) as usize],
+            0xE000..=0xFDFF => self.wram[(addr - ECHO_OFFSET) as usize], // Mirrors 0xC000 -> 0xDDFF
             0xFE00..=0xFE9F => self.oam[(addr - OAM_OFFSET) as usize],
+            0xFEA0..=0xFEFF => self.read_unusable(addr),
+            0xFF00..=0xFF7F => self.read_io(addr),
             0xFF80..=0xFFFE => self.hram[(addr - HRAM_OFFSET) as usize],
-            0xFFFF => self.interrupts_enabled as u8,
-            _ => 0,
+            IE_ADDR => self.interrupt_enable,
         }
     }
 
     fn write(&mut self, addr: u16, data: u8) {
-        // TODO: Implement memory mapping
         match addr {
-            0x0000..=0x7FFF => self.cartridge.rom[addr as usize] = data,
+            0x0000..=0x7FFF => self.cartridge.write_rom(addr, data),
             0x8000..=0x9FFF => self.vram[(addr - VRAM_OFFSET) as usize] = data,
+            0xA000..=0xBFFF => self.cartridge.write_ram(addr, data),
             0xC000..=0xDFFF => self.wram[(addr - WRAM_OFFSET) as usize] = data,
+            0xE000..=0xFDFF => self.wram[(addr - ECHO_OFFSET) as usize] = data,
             0xFE00..=0xFE9F => self.oam[(addr - OAM_OFFSET) as usize] = data,
+            0xFEA0..=0xFEFF => {}
+            0xFF00..=0xFF7F => self.write_io(addr, data),
             0xFF80..=0xFFFE => self.hram[(addr - HRAM_OFFSET) as usize] = data,
-            0xFFFF => {
-                if data == 1 {
-                    self.enable_interrupts();
-                } else {
-                    self.disable_interrupts();
-                }
-            }
-            _ => {}
+            IE_ADDR => self.interrupt_enable = data,
         }
     }
 
-    fn request_interrupt(&mut self) {
-        unimplemented!("TODO: implement interrupt requests")
+    fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag |= interrupt.mask();
     }
 
-    fn enable_interrupts(&mut self) {
-        self.interrupts_enabled = true;
+    fn tick(&mut self) {
+        if self.timer.tick() {
+            self.request_interrupt(Interrupt::Timer);
+        }
+
+        self.cartridge.tick(if self.double_speed { 2 } else { 4 });
     }
 
-    fn disable_interrupts(&mut self) {
-        self.interrupts_enabled = false;
+    fn speed_switch(&mut self) -> bool {
+        if !self.speed_switch_armed {
+            return false;
+        }
+
+        self.speed_switch_armed = false;
+        self.double_speed = !self.double_speed;
+
+        true
     }
 
-    fn interrupts_enabled(&self) -> bool {
-        self.interrupts_enabled
+    fn code_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x7FFF | 0xA000..=0xBFFF => self.cartridge.bank(addr),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_bus(model: Model) -> SystemBus {
+        let mut rom = vec![0; 0x8000];
+        rom[0x149] = 0x02; // 8 KiB of cartridge RAM
+
+        SystemBus::from_rom(rom, model)
+    }
+
+    #[test]
+    fn test_echo_ram_mirrors_wram() {
+        let mut bus = make_bus(Model::Dmg);
+
+        bus.write(0xC123, 0x42);
+        assert_eq!(bus.read(0xE123), 0x42);
+
+        bus.write(0xFDFF, 0x24);
+        assert_eq!(bus.read(0xDDFF), 0x24);
+    }
+
+    #[test]
+    fn test_unused_io_bits_read_as_one() {
+        let mut bus = make_bus(Model::Dmg);
+
+        bus.write(0xFF03, 0x00); // Unmapped
+        bus.write(0xFF41, 0x00); // STAT
+        bus.write(0xFF4D, 0x00); // KEY1 only exists on CGB
+        bus.write(P1_ADDR, 0x10);
+
+        assert_eq!(bus.read(0xFF03), 0xFF);
+        assert_eq!(bus.read(0xFF41), 0x80);
+        assert_eq!(bus.read(0xFF4D), 0xFF);
+        assert_eq!(bus.read(P1_ADDR), 0xDF);
+        assert_eq!(bus.read(0xFF30), 0x00); // Wave RAM uses every bit
+    }
+
+    #[test]
+    fn test_button_press_raises_joypad_interrupt() {
+        let mut bus = make_bus(Model::Dmg);
+        bus.write(P1_ADDR, 0x10); // Buttons
+        bus.write(IF_ADDR, 0x00);
+
+        bus.press_button(Button::Down);
+        assert_eq!(bus.read(IF_ADDR), 0xE0); // Not selected
+        assert_eq!(bus.read(P1_ADDR), 0xDF);
+
+        bus.press_button(Button::B);
+        assert_eq!(bus.read(IF_ADDR), 0xE0 | Interrupt::Joypad.mask());
+        assert_eq!(bus.read(P1_ADDR), 0xDD);
+
+        bus.release_button(Button::B);
+        assert_eq!(bus.read(P1_ADDR), 0xDF);
+    }
+
+    #[test]
+    fn test_cartridge_ram_and_unusable_area() {
+        let mut bus = make_bus(Model::Dmg);
+
+        bus.write(0xA000, 0x99);
+        bus.write(0xFEA0, 0x99);
+        bus.write(0x0000, 0x99);
+
+        assert_eq!(bus.read(0xA000), 0x99);
+        assert_eq!(bus.read(0xFEA0), 0x00);
+        assert_eq!(bus.read(0x0000), 0x00);
+
+        assert_eq!(make_bus(Model::Cgb).read(0xFEB4), 0xBB);
+    }
+
+    #[test]
+    fn test_rumble_events_reach_the_bus() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x147] = 0x1C; // MBC5 + rumble
+        let mut bus = SystemBus::from_rom(rom, Model::Dmg);
+
+        bus.write(0x4000, 0x08);
+        bus.write(0x4000, 0x00);
+
+        assert_eq!(
+            bus.take_cartridge_events(),
+            [CartridgeEvent::Rumble(true), CartridgeEvent::Rumble(false)]
+        );
+        assert!(bus.take_cartridge_events().is_empty());
     }
 }
@@ -0,0 +1,108 @@
+use crate::bus::SystemBus;
+use crate::model::Model;
+
+pub const IO_SIZE: usize = 128;
+pub const IO_OFFSET: u16 = 0xFF00;
+
+// How an address in 0xFF00 -> 0xFF7F is handled, peripherals register these into SystemBus' table
+#[derive(Clone, Copy)]
+pub enum IoRegister {
+    // Nothing responds, reads see an open bus
+    Unmapped,
+    // Plain storage for registers without hardware behind them yet. Bits set in `unused` read as 1
+    Stored {
+        unused: u8,
+    },
+    Device {
+        read: fn(&SystemBus, u16) -> u8,
+        write: fn(&mut SystemBus, u16, u8),
+    },
+}
+
+/*
+    Registers that read back what was written, other than their unused bits.
+    Write-only bits (e.g. NRx4 bits 0-2, NR13) also read as 1 on hardware, so they are included.
+*/
+const DMG_STORED_REGISTERS: &[(u16, u8)] = &[
+    (0xFF01, 0x00), // SB
+    (0xFF02, 0x7E), // SC
+    (0xFF10, 0x80), // NR10
+    (0xFF11, 0x3F), // NR11
+    (0xFF12, 0x00), // NR12
+    (0xFF13, 0xFF), // NR13
+    (0xFF14, 0xBF), // NR14
+    (0xFF16, 0x3F), // NR21
+    (0xFF17, 0x00), // NR22
+    (0xFF18, 0xFF), // NR23
+    (0xFF19, 0xBF), // NR24
+    (0xFF1A, 0x7F), // NR30
+    (0xFF1B, 0xFF), // NR31
+    (0xFF1C, 0x9F), // NR32
+    (0xFF1D, 0xFF), // NR33
+    (0xFF1E, 0xBF), // NR34
+    (0xFF20, 0xFF), // NR41
+    (0xFF21, 0x00), // NR42
+    (0xFF22, 0x00), // NR43
+    (0xFF23, 0xBF), // NR44
+    (0xFF24, 0x00), // NR50
+    (0xFF25, 0x00), // NR51
+    (0xFF26, 0x70), // NR52
+    (0xFF40, 0x00), // LCDC
+    (0xFF41, 0x80), // STAT
+    (0xFF42, 0x00), // SCY
+    (0xFF43, 0x00), // SCX
+    (0xFF44, 0x00), // LY
+    (0xFF45, 0x00), // LYC
+    (0xFF46, 0x00), // DMA
+    (0xFF47, 0x00), // BGP
+    (0xFF48, 0x00), // OBP0
+    (0xFF49, 0x00), // OBP1
+    (0xFF4A, 0x00), // WY
+    (0xFF4B, 0x00), // WX
+];
+
+// HDMA1-4 are write-only, so they read as 0xFF
+const CGB_STORED_REGISTERS: &[(u16, u8)] = &[
+    (0xFF02, 0x7C), // SC
+    (0xFF4F, 0xFE), // VBK
+    (0xFF51, 0xFF), // HDMA1
+    (0xFF52, 0xFF), // HDMA2
+    (0xFF53, 0xFF), // HDMA3
+    (0xFF54, 0xFF), // HDMA4
+    (0xFF55, 0x00), // HDMA5
+    (0xFF56, 0x3C), // RP
+    (0xFF68, 0x40), // BCPS
+    (0xFF69, 0x00), // BCPD
+    (0xFF6A, 0x40), // OCPS
+    (0xFF6B, 0x00), // OCPD
+    (0xFF6C, 0xFE), // OPRI
+    (0xFF70, 0xF8), // SVBK
+    (0xFF72, 0x00),
+    (0xFF73, 0x00),
+    (0xFF74, 0x00),
+    (0xFF75, 0x8F),
+    (0xFF76, 0x00), // PCM12
+    (0xFF77, 0x00), // PCM34
+];
+
+// Table with every register that has no peripheral behind it yet, the rest start unmapped
+pub fn stored_registers(model: Model) -> [IoRegister; IO_SIZE] {
+    let mut registers = [IoRegister::Unmapped; IO_SIZE];
+
+    let cgb_registers: &[(u16, u8)] = if model.is_cgb() {
+        CGB_STORED_REGISTERS
+    } else {
+        &[]
+    };
+
+    for (addr, unused) in DMG_STORED_REGISTERS.iter().chain(cgb_registers) {
+        registers[(addr - IO_OFFSET) as usize] = IoRegister::Stored { unused: *unused };
+    }
+
+    // Wave RAM
+    for addr in 0xFF30..=0xFF3F {
+        registers[addr - IO_OFFSET as usize] = IoRegister::Stored { unused: 0x00 };
+    }
+
+    registers
+}
@@ -0,0 +1,104 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    // The d-pad is read with P1 bit 4 low and the other buttons with bit 5 low
+    fn mask(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+/*
+    P1 selects which group of buttons drives its low 4 bits.
+    Lines are pulled low (0) by a pressed button, and are shared when both groups are selected.
+*/
+pub struct Joypad {
+    select: u8,  // P1 bits 4 and 5
+    pressed: u8, // d-pad in the low nibble, other buttons in the high nibble
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Self {
+            select: 0x30,
+            pressed: 0,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | self.lines()
+    }
+
+    // Returns true when a line goes low, which raises the joypad interrupt
+    pub fn write(&mut self, data: u8) -> bool {
+        self.update(|joypad| joypad.select = data & 0x30)
+    }
+
+    pub fn press(&mut self, button: Button) -> bool {
+        self.update(|joypad| joypad.pressed |= button.mask())
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.pressed &= !button.mask();
+    }
+
+    fn lines(&self) -> u8 {
+        let mut low = 0;
+
+        if self.select & 0x10 == 0 {
+            low |= self.pressed & 0x0F;
+        }
+
+        if self.select & 0x20 == 0 {
+            low |= self.pressed >> 4;
+        }
+
+        !low & 0x0F
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut Self)) -> bool {
+        let old_lines = self.lines();
+        change(self);
+
+        old_lines & !self.lines() != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selected_group_drives_the_lines() {
+        let mut joypad = Joypad::new();
+
+        assert!(!joypad.press(Button::Start)); // Nothing selected yet
+        assert_eq!(joypad.read(), 0xFF);
+
+        assert!(!joypad.write(0x20)); // D-pad
+        assert_eq!(joypad.read(), 0xEF);
+
+        assert!(joypad.write(0x10)); // Buttons, Start pulls line 3 low
+        assert_eq!(joypad.read(), 0xD7);
+
+        assert!(joypad.press(Button::A));
+        assert_eq!(joypad.read(), 0xD6);
+
+        // Already low through A, so pressing Right doesn't raise another interrupt
+        assert!(!joypad.write(0x00));
+        assert!(!joypad.press(Button::Right));
+
+        joypad.release(Button::A);
+        joypad.release(Button::Right);
+        assert_eq!(joypad.read(), 0xC7);
+    }
+}
@@ -1,33 +1,227 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
 
 use crate::bus::SystemBus;
-use crate::cpu::CPU;
+use crate::save::SaveFile;
 
 mod bus;
 #[path = "Cartridge/mod.rs"]
 mod cartridge;
 #[path = "CPU/mod.rs"]
 mod cpu;
+mod io;
+mod joypad;
+mod model;
+mod save;
+mod timer;
+
+pub use bus::{Bus, Interrupt};
+pub use cartridge::{
+    CAMERA_HEIGHT, CAMERA_WIDTH, CameraFrame, CartridgeEvent, CartridgeHeader, CgbSupport,
+    Destination, HeaderError, ImageSource, InfraredPort, LoadError, MapperKind, NoInfrared,
+    RtcClock, StaticImage, TestPattern,
+};
+pub use cpu::CPU;
+pub use cpu::assembler::{AssembleError, Assembly, Section, SectionType, assemble};
+pub use cpu::disassembler::{DisassembledInstruction, disassemble, disassemble_instruction};
+pub use cpu::instructions::{Condition, FlagEffect, FlagEffects, Instruction, Operand};
+pub use cpu::{CpuState, FlagRegister, IllegalOpcode, Register, RunState};
+pub use joypad::Button;
+pub use model::Model;
 
-#[allow(dead_code)]
 pub struct Emulator {
-    bus: Rc<RefCell<SystemBus>>,
-    cpu: CPU,
+    cpu: CPU<SystemBus>,
+    save_file: Option<SaveFile>, // Where battery backed memory is kept, for carts loaded from a file
 }
 
-#[allow(dead_code)]
 impl Emulator {
+    /*
+        Starts execution at the cartridge entry point, in the state the model's boot ROM leaves behind.
+        There's no save file, so battery backed memory goes through save_data / load_save_data.
+    */
+    pub fn from_rom_bytes(rom: impl Into<Vec<u8>>, model: Model) -> Result<Self, LoadError> {
+        let mut cpu = CPU::new(SystemBus::load(rom.into(), model)?);
+        cpu.skip_boot_rom(model);
+
+        Ok(Self {
+            cpu,
+            save_file: None,
+        })
+    }
+
+    pub fn from_reader(mut reader: impl Read, model: Model) -> Result<Self, LoadError> {
+        let mut rom = Vec::new();
+        reader.read_to_end(&mut rom).map_err(LoadError::Io)?;
+
+        Self::from_rom_bytes(rom, model)
+    }
+
+    // Carts with a battery keep their save in a .sav file next to the ROM
+    pub fn from_path(path: impl AsRef<Path>, model: Model) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+
+        let file = File::open(path).map_err(|error| match error.kind() {
+            ErrorKind::NotFound => LoadError::NotFound(path.to_path_buf()),
+            _ => LoadError::Io(error),
+        })?;
+
+        let mut emulator = Self::from_reader(file, model)?;
+        emulator
+            .open_save_file(path.with_extension("sav"))
+            .map_err(LoadError::Io)?;
+
+        Ok(emulator)
+    }
+
+    #[deprecated(note = "use Emulator::from_path, which reports load errors instead of panicking")]
     pub fn new(file_name: &str) -> Self {
-        let bus = Rc::new(RefCell::new(SystemBus::new(file_name)));
-        let cpu = CPU::new(bus.clone());
+        Self::from_path(file_name, Model::Dmg)
+            .unwrap_or_else(|why| panic!("Couldn't load {file_name}: {why}"))
+    }
+
+    fn open_save_file(&mut self, path: PathBuf) -> std::io::Result<()> {
+        if self.save_data().is_none() {
+            return Ok(());
+        }
 
-        Self { bus, cpu }
+        let (save_file, data) = SaveFile::open(path)?;
+        if let Some(data) = data {
+            self.load_save_data(&data);
+        }
+
+        self.save_file = Some(save_file);
+        Ok(())
+    }
+
+    /*
+        Writes the .sav file if the save changed since it was last written.
+        This also happens when the emulator is dropped, and about once a second of emulated time
+        if memory other than a cartridge clock changed, but errors are only reported from here.
+    */
+    pub fn flush_save(&mut self) -> std::io::Result<()> {
+        self.write_save(0)
+    }
+
+    fn write_save(&mut self, clock_size: usize) -> std::io::Result<()> {
+        let Some(save_file) = &mut self.save_file else {
+            return Ok(());
+        };
+
+        let data = self.cpu.bus().save_data().unwrap_or_default();
+        save_file.flush(&data, clock_size, self.cpu.cycles())
+    }
+
+    // Cartridge clocks follow emulated time by default
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.cpu.bus_mut().set_rtc_clock(clock);
     }
 
-    pub fn execute(&mut self) {
+    // Parsed header of the loaded cartridge
+    pub fn cartridge_header(&self) -> &CartridgeHeader {
+        self.cpu.bus().cartridge_header()
+    }
+
+    // Connects the infrared LED and sensor of HuC1 / HuC3 carts, which see no light by default
+    pub fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.cpu.bus_mut().set_infrared_port(port);
+    }
+
+    // Where the Game Boy Camera's pictures come from, a test pattern by default
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.cpu.bus_mut().set_image_source(source);
+    }
+
+    // Held buttons wake the CPU from STOP and raise the joypad interrupt when P1 selects them
+    pub fn press_button(&mut self, button: Button) {
+        self.cpu.bus_mut().press_button(button);
+    }
+
+    pub fn release_button(&mut self, button: Button) {
+        self.cpu.bus_mut().release_button(button);
+    }
+
+    /*
+        Feeds the accelerometer of tilt sensing carts (MBC7), in g along each axis.
+        X grows when tilting right and Y when tilting towards the bottom of the screen.
+        Holding the console level is (0, 0).
+    */
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.cpu.bus_mut().set_tilt(x, y);
+    }
+
+    // Contents of the cartridge's battery backed memory, None if it has no battery
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        self.cpu.bus().save_data()
+    }
+
+    // Restores a save, e.g. from another emulator, before the game reads it
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.cpu.memory_mut().load_save_data(data);
+    }
+
+    /*
+        Events like rumble motor changes, oldest first, since the last call.
+        They queue up until taken, so frontends should call this every frame.
+    */
+    pub fn take_cartridge_events(&mut self) -> Vec<CartridgeEvent> {
+        self.cpu.bus_mut().take_cartridge_events()
+    }
+
+    // Logs every executed instruction to the sink in the gameboy-doctor format
+    pub fn set_trace(&mut self, sink: Option<Box<dyn Write>>) {
+        self.cpu.set_trace(sink);
+    }
+
+    // Runs from decoded blocks of code instead of decoding every instruction as it's fetched
+    pub fn set_block_cache(&mut self, enabled: bool) {
+        self.cpu.set_block_cache(enabled);
+    }
+
+    // Runs a single instruction (or one idle M-cycle while halted or stopped)
+    pub fn step(&mut self) -> Result<(), IllegalOpcode> {
+        self.cpu.tick();
+
+        // Failed writes are retried at the next check
+        if self
+            .save_file
+            .as_ref()
+            .is_some_and(|save_file| save_file.due(self.cpu.cycles()))
+        {
+            let _ = self.write_save(self.cpu.bus().clock_save_size());
+        }
+
+        match self.cpu.run_state() {
+            RunState::Locked(error) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    // Lets frontends and debuggers show why the CPU isn't executing
+    pub fn run_state(&self) -> RunState {
+        self.cpu.run_state()
+    }
+
+    // Snapshot of the registers, IME, run state and cycle count
+    pub fn cpu_state(&self) -> CpuState {
+        self.cpu.state()
+    }
+
+    // For test setup and debugger commands, meant to be used between steps
+    pub fn set_register(&mut self, register: Register, value: u16) {
+        self.cpu.set_register(register, value);
+    }
+
+    // Runs until the CPU locks up
+    pub fn execute(&mut self) -> Result<(), IllegalOpcode> {
         loop {
-            self.cpu.tick();
+            self.step()?;
         }
     }
 }
+
+impl Drop for Emulator {
+    fn drop(&mut self) {
+        let _ = self.flush_save();
+    }
+}
@@ -0,0 +1,144 @@
+use std::str::FromStr;
+
+// Console revisions that differ in the state their boot ROM leaves behind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    Dmg0,
+    Dmg,
+    Mgb,
+    Sgb,
+    Sgb2,
+    Cgb,
+    Agb,
+}
+
+/*
+    IO registers as the boot ROM leaves them, taken from the Pan Docs power up tables.
+    Registers the docs list as unknown / randomised (STAT and LY on later models, OBP0/OBP1)
+    are left at 0. DIV is handled separately since it lives in the timer's internal counter.
+*/
+const DMG_IO_REGISTERS: &[(u16, u8)] = &[
+    (0xFF00, 0xCF), // P1
+    (0xFF01, 0x00), // SB
+    (0xFF02, 0x7E), // SC
+    (0xFF05, 0x00), // TIMA
+    (0xFF06, 0x00), // TMA
+    (0xFF07, 0xF8), // TAC
+    (0xFF0F, 0xE1), // IF
+    (0xFF10, 0x80), // NR10
+    (0xFF11, 0xBF), // NR11
+    (0xFF12, 0xF3), // NR12
+    (0xFF13, 0xFF), // NR13
+    (0xFF14, 0xBF), // NR14
+    (0xFF16, 0x3F), // NR21
+    (0xFF17, 0x00), // NR22
+    (0xFF18, 0xFF), // NR23
+    (0xFF19, 0xBF), // NR24
+    (0xFF1A, 0x7F), // NR30
+    (0xFF1B, 0xFF), // NR31
+    (0xFF1C, 0x9F), // NR32
+    (0xFF1D, 0xFF), // NR33
+    (0xFF1E, 0xBF), // NR34
+    (0xFF20, 0xFF), // NR41
+    (0xFF21, 0x00), // NR42
+    (0xFF22, 0x00), // NR43
+    (0xFF23, 0xBF), // NR44
+    (0xFF24, 0x77), // NR50
+    (0xFF25, 0xF3), // NR51
+    (0xFF26, 0xF1), // NR52
+    (0xFF40, 0x91), // LCDC
+    (0xFF42, 0x00), // SCY
+    (0xFF43, 0x00), // SCX
+    (0xFF45, 0x00), // LYC
+    (0xFF46, 0xFF), // DMA
+    (0xFF47, 0xFC), // BGP
+    (0xFF4A, 0x00), // WY
+    (0xFF4B, 0x00), // WX
+    (0xFFFF, 0x00), // IE
+];
+
+// Registers that only exist on CGB hardware, or that the CGB boot ROM leaves differently
+const CGB_IO_REGISTERS: &[(u16, u8)] = &[
+    (0xFF02, 0x7F), // SC
+    (0xFF46, 0x00), // DMA
+    (0xFF4D, 0x7E), // KEY1
+    (0xFF4F, 0xFE), // VBK
+    (0xFF51, 0xFF), // HDMA1
+    (0xFF52, 0xFF), // HDMA2
+    (0xFF53, 0xFF), // HDMA3
+    (0xFF54, 0xFF), // HDMA4
+    (0xFF55, 0xFF), // HDMA5
+    (0xFF56, 0x3E), // RP
+    (0xFF70, 0xF8), // SVBK
+];
+
+impl Model {
+    pub fn is_cgb(self) -> bool {
+        matches!(self, Model::Cgb | Model::Agb)
+    }
+
+    pub fn is_sgb(self) -> bool {
+        matches!(self, Model::Sgb | Model::Sgb2)
+    }
+
+    // Every (address, value) pair the boot ROM leaves in the IO region, later entries win
+    pub(crate) fn io_registers(self) -> impl Iterator<Item = (u16, u8)> {
+        let model_specific: &[(u16, u8)] = match self {
+            Model::Dmg0 => &[(0xFF41, 0x81), (0xFF44, 0x91)], // STAT, LY
+            Model::Dmg | Model::Mgb => &[(0xFF41, 0x85)],     // STAT
+            Model::Sgb | Model::Sgb2 => &[(0xFF26, 0xF0)],    // NR52
+            Model::Cgb | Model::Agb => CGB_IO_REGISTERS,
+        };
+
+        DMG_IO_REGISTERS.iter().chain(model_specific).copied()
+    }
+
+    /*
+        Value of the timer's 16 bit internal counter (DIV is its upper byte) when the boot ROM exits.
+        It depends on how long the boot ROM ran. Only DIV itself is known for DMG0,
+        and nothing is pinned down for the SGB and CGB models.
+    */
+    pub(crate) fn div_counter(self) -> u16 {
+        match self {
+            Model::Dmg0 => 0x1800,
+            Model::Dmg | Model::Mgb => 0xABCC,
+            Model::Sgb | Model::Sgb2 | Model::Cgb | Model::Agb => 0x0000,
+        }
+    }
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "dmg0" => Ok(Model::Dmg0),
+            "dmg" => Ok(Model::Dmg),
+            "mgb" => Ok(Model::Mgb),
+            "sgb" => Ok(Model::Sgb),
+            "sgb2" => Ok(Model::Sgb2),
+            "cgb" => Ok(Model::Cgb),
+            "agb" => Ok(Model::Agb),
+            _ => Err(format!("Unknown model {name}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_specific_io_registers_override_dmg() {
+        let sc = |model: Model| {
+            model
+                .io_registers()
+                .filter(|(addr, _)| *addr == 0xFF02)
+                .last()
+                .map(|(_, value)| value)
+        };
+
+        assert_eq!(sc(Model::Dmg), Some(0x7E));
+        assert_eq!(sc(Model::Cgb), Some(0x7F));
+    }
+}
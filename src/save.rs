@@ -0,0 +1,192 @@
+use std::{fs, io, path::PathBuf};
+
+// M-cycles between checks for unsaved changes, about a second of emulated time
+pub const SAVE_CHECK_INTERVAL: u64 = 1024 * 1024;
+
+/*
+    Keeps a .sav file in step with the cartridge's battery backed memory. The file holds the raw
+    save_data layout, which is what other emulators read and write too. Rather than every mapper
+    tracking writes, changes are found by comparing against what was last written out.
+*/
+pub struct SaveFile {
+    path: PathBuf,
+    flushed: Option<Vec<u8>>, // Contents of the file, None if it hasn't been written or read yet
+    next_check: u64,          // CPU cycle count of the next dirty check
+}
+
+impl SaveFile {
+    // Reads the existing save, if there is one
+    pub fn open(path: PathBuf) -> io::Result<(Self, Option<Vec<u8>>)> {
+        let data = match fs::read(&path) {
+            Ok(data) => Some(data),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+
+        let save = Self {
+            path,
+            flushed: data.clone(),
+            next_check: SAVE_CHECK_INTERVAL,
+        };
+
+        Ok((save, data))
+    }
+
+    pub fn due(&self, cycles: u64) -> bool {
+        cycles >= self.next_check
+    }
+
+    /*
+        Writes `data` out if it changed since the last flush. The last `clock_size` bytes hold a
+        cartridge clock, which changes every second, so changes there alone don't count.
+    */
+    pub fn flush(&mut self, data: &[u8], clock_size: usize, cycles: u64) -> io::Result<()> {
+        self.next_check = cycles + SAVE_CHECK_INTERVAL;
+
+        let unchanged = self.flushed.as_deref().is_some_and(|flushed| {
+            let compared = data.len().saturating_sub(clock_size);
+            flushed.len() == data.len() && flushed[..compared] == data[..compared]
+        });
+        if unchanged {
+            return Ok(());
+        }
+
+        fs::write(&self.path, data)?;
+        self.flushed = Some(data.to_vec());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::{NINTENDO_LOGO, header_checksum};
+    use crate::{Bus, Emulator, Model, RtcClock};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gb-core-{}-{}", std::process::id(), name))
+    }
+
+    // A cart with 8 KiB of RAM that spins on `jr @` at its entry point
+    fn write_rom(name: &str, cartridge_type: u8) -> PathBuf {
+        let path = temp_path(name);
+
+        let mut rom = vec![0; 0x8000];
+        rom[0x100..0x102].copy_from_slice(&[0x18, 0xFE]);
+        rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+        rom[0x147] = cartridge_type;
+        rom[0x149] = 0x02;
+        rom[0x14D] = header_checksum(&rom);
+        fs::write(&path, rom).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_flush_only_writes_changes() {
+        let path = temp_path("flush.sav");
+        let (mut save, data) = SaveFile::open(path.clone()).unwrap();
+        assert_eq!(data, None);
+        assert!(!save.due(0));
+        assert!(save.due(SAVE_CHECK_INTERVAL));
+
+        save.flush(&[1, 2, 3], 0, SAVE_CHECK_INTERVAL).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), [1, 2, 3]);
+        assert!(!save.due(SAVE_CHECK_INTERVAL));
+
+        // Unchanged data isn't written again
+        fs::remove_file(&path).unwrap();
+        save.flush(&[1, 2, 3], 0, 2 * SAVE_CHECK_INTERVAL).unwrap();
+        assert!(!path.exists());
+
+        // Nor is data that only differs in the clock
+        save.flush(&[1, 2, 4], 1, 3 * SAVE_CHECK_INTERVAL).unwrap();
+        assert!(!path.exists());
+
+        save.flush(&[1, 2, 4], 0, 4 * SAVE_CHECK_INTERVAL).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), [1, 2, 4]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_save_survives_restart() {
+        let rom_path = write_rom("game.gb", 0x03); // MBC1 + RAM + battery
+        let save_path = rom_path.with_extension("sav");
+
+        let mut emulator = Emulator::from_path(&rom_path, Model::Dmg).unwrap();
+        emulator.cpu.memory_mut().write(0x0000, 0x0A);
+        emulator.cpu.memory_mut().write(0xA123, 0x42);
+        drop(emulator);
+
+        let save = fs::read(&save_path).unwrap();
+        assert_eq!(save.len(), 0x2000);
+        assert_eq!(save[0x123], 0x42);
+
+        let emulator = Emulator::from_path(&rom_path, Model::Dmg).unwrap();
+        assert_eq!(emulator.save_data().unwrap()[0x123], 0x42);
+
+        drop(emulator);
+        fs::remove_file(rom_path).unwrap();
+        fs::remove_file(save_path).unwrap();
+    }
+
+    #[test]
+    fn test_clock_catches_up_when_host_time_is_selected_after_loading() {
+        let rom_path = write_rom("clock.gb", 0x10); // MBC3 + timer + RAM + battery
+        let save_path = rom_path.with_extension("sav");
+
+        // 8 KiB of RAM, then a clock at 5 hours that was saved an hour ago
+        let mut save = vec![0; 0x2000 + 48];
+        save[0x2008] = 5;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 3600;
+        save[0x2028..].copy_from_slice(&timestamp.to_le_bytes());
+        fs::write(&save_path, save).unwrap();
+
+        let mut emulator = Emulator::from_path(&rom_path, Model::Dmg).unwrap();
+        emulator.set_rtc_clock(RtcClock::Host);
+
+        let bus = emulator.cpu.memory_mut();
+        bus.write(0x0000, 0x0A);
+        bus.write(0x4000, 0x0A); // Hours
+        bus.write(0x6000, 0x00);
+        bus.write(0x6000, 0x01);
+        assert_eq!(bus.read(0xA000), 6);
+
+        drop(emulator);
+        fs::remove_file(rom_path).unwrap();
+        fs::remove_file(save_path).unwrap();
+    }
+
+    #[test]
+    fn test_running_clock_alone_doesnt_rewrite_the_save() {
+        let rom_path = write_rom("idle.gb", 0x10); // MBC3 + timer + RAM + battery
+        let save_path = rom_path.with_extension("sav");
+
+        let mut emulator = Emulator::from_path(&rom_path, Model::Dmg).unwrap();
+        emulator.flush_save().unwrap();
+        fs::remove_file(&save_path).unwrap();
+
+        // A few emulated seconds, each of which ticks the clock
+        while emulator.cpu.cycles() < 4 * SAVE_CHECK_INTERVAL {
+            emulator.step().unwrap();
+        }
+        assert!(!save_path.exists());
+
+        emulator.cpu.memory_mut().write(0x0000, 0x0A);
+        emulator.cpu.memory_mut().write(0xA000, 0x42);
+        while emulator.cpu.cycles() < 6 * SAVE_CHECK_INTERVAL {
+            emulator.step().unwrap();
+        }
+        assert_eq!(fs::read(&save_path).unwrap()[0], 0x42);
+
+        drop(emulator);
+        fs::remove_file(rom_path).unwrap();
+        fs::remove_file(save_path).unwrap();
+    }
+}
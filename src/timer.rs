@@ -0,0 +1,155 @@
+pub const DIV_ADDR: u16 = 0xFF04;
+pub const TIMA_ADDR: u16 = 0xFF05;
+pub const TMA_ADDR: u16 = 0xFF06;
+pub const TAC_ADDR: u16 = 0xFF07;
+
+/*
+    DIV is the upper byte of a 16 bit counter that advances every T-cycle.
+    TIMA increments on the falling edge of the counter bit selected by TAC,
+    which is why writes to DIV and TAC can also bump TIMA.
+*/
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    reload_pending: bool, // TIMA overflowed last cycle and is reloaded from TMA on the next
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            reload_pending: false,
+        }
+    }
+
+    // Used to start from the state the boot ROM leaves behind
+    pub fn set_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    // Advances the timer by one M-cycle, returning true when the timer interrupt should be raised
+    pub fn tick(&mut self) -> bool {
+        let mut interrupt = false;
+
+        if self.reload_pending {
+            self.reload_pending = false;
+            self.tima = self.tma;
+            interrupt = true;
+        }
+
+        let old_counter = self.counter;
+        self.counter = self.counter.wrapping_add(4);
+        self.detect_falling_edge(old_counter, self.tac);
+
+        interrupt
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            DIV_ADDR => (self.counter >> 8) as u8,
+            TIMA_ADDR => self.tima,
+            TMA_ADDR => self.tma,
+            TAC_ADDR => self.tac | 0xF8,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            DIV_ADDR => {
+                let old_counter = self.counter;
+                self.counter = 0;
+                self.detect_falling_edge(old_counter, self.tac);
+            }
+            TIMA_ADDR => {
+                // Writing TIMA during the reload delay cancels the reload
+                self.tima = data;
+                self.reload_pending = false;
+            }
+            TMA_ADDR => self.tma = data,
+            TAC_ADDR => {
+                let old_tac = self.tac;
+                self.tac = data & 0x07;
+                self.detect_falling_edge(self.counter, old_tac);
+            }
+            _ => {}
+        }
+    }
+
+    fn timer_bit(counter: u16, tac: u8) -> bool {
+        let bit = match tac & 0x03 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            _ => 7,
+        };
+
+        tac & 0x04 != 0 && counter & (1 << bit) != 0
+    }
+
+    fn detect_falling_edge(&mut self, old_counter: u16, old_tac: u8) {
+        if Self::timer_bit(old_counter, old_tac) && !Self::timer_bit(self.counter, self.tac) {
+            let (value, overflowed) = self.tima.overflowing_add(1);
+
+            self.tima = value;
+            self.reload_pending = overflowed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_increments_every_64_cycles() {
+        let mut timer = Timer::new();
+
+        for _ in 0..64 {
+            timer.tick();
+        }
+
+        assert_eq!(timer.read(DIV_ADDR), 1);
+
+        timer.write(DIV_ADDR, 0x55);
+        assert_eq!(timer.read(DIV_ADDR), 0);
+    }
+
+    #[test]
+    fn test_tima_overflow_reloads_from_tma() {
+        let mut timer = Timer::new();
+        timer.write(TAC_ADDR, 0x05); // Enabled, increments every 4 M-cycles
+        timer.write(TMA_ADDR, 0xAB);
+        timer.write(TIMA_ADDR, 0xFF);
+
+        let mut interrupts = 0;
+        for _ in 0..4 {
+            interrupts += timer.tick() as u32;
+        }
+
+        assert_eq!(timer.read(TIMA_ADDR), 0x00);
+        assert_eq!(interrupts, 0);
+
+        assert!(timer.tick());
+        assert_eq!(timer.read(TIMA_ADDR), 0xAB);
+    }
+
+    #[test]
+    fn test_div_reset_can_increment_tima() {
+        let mut timer = Timer::new();
+        timer.write(TAC_ADDR, 0x05);
+
+        for _ in 0..2 {
+            timer.tick();
+        }
+
+        timer.write(DIV_ADDR, 0);
+
+        assert_eq!(timer.read(TIMA_ADDR), 1);
+    }
+}